@@ -0,0 +1,47 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use tsp_parser::{
+    DenseMatrix, Distances, NearestNeighbour, PackedMatrix, Tsp, TspHeuristic, TspParser, TwoOpt,
+};
+
+const PROBLEM_FILE: &str = "test_files/d1655.tsp";
+
+/// `TwoOpt` from the same nearest neighbour route, with edge weights kept in
+/// each storage variant.
+fn two_opt_route(c: &mut Criterion) {
+    let tsp = TspParser::from_file(PROBLEM_FILE).expect("Couldn't parse test file");
+    let dimension = tsp.get_dimension();
+
+    let dense = Tsp::new(
+        Distances::Matrix(DenseMatrix::from_fn(dimension, |from, to| {
+            tsp.get_edge(from, to)
+        })),
+        dimension,
+        tsp.get_tsp_type(),
+    );
+    let packed = Tsp::new(
+        Distances::Packed(PackedMatrix::from_fn(dimension, |from, to| {
+            tsp.get_edge(from, to)
+        })),
+        dimension,
+        tsp.get_tsp_type(),
+    );
+
+    let two_opt = TwoOpt::new(NearestNeighbour);
+
+    let mut group = c.benchmark_group("d1655 2-opt");
+    group.sample_size(10);
+
+    for (name, tsp) in [
+        ("dense matrix", &dense),
+        ("packed matrix", &packed),
+        ("coordinates", &tsp),
+    ] {
+        group.bench_function(name, |b| b.iter(|| two_opt.get_route(tsp)));
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, two_opt_route);
+criterion_main!(benches);
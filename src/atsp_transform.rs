@@ -0,0 +1,241 @@
+use crate::distances::Distances;
+use crate::matrix::DenseMatrix;
+use crate::tsp::{Tsp, TspType};
+use crate::tsp_parser::{ProblemType, TspHeader};
+use crate::weight::Weight;
+
+/// Symmetric instance with twice the nodes of an asymmetric one, by the
+/// Jonker–Volgenant node doubling, so symmetric heuristics can solve ATSP.
+///
+/// Every node `i` gets a twin `i + n` joined to it by a zero weight edge, an
+/// arc `i -> j` becomes the edge between `i + n` and `j` weighing
+/// `d(i, j) + offset` and all other edges weigh `2 * offset`. The offset is
+/// bigger than any asymmetric route, so good routes alternate between nodes
+/// and their twins and weigh `n * offset` more than the asymmetric route.
+/// Fixed arcs stay fixed as their edges.
+#[derive(Debug, Clone)]
+pub struct SymmetricTransform<W = u32> {
+    tsp: Tsp<W>,
+    dimension: usize,
+    offset: W,
+}
+
+impl<W: Weight> SymmetricTransform<W> {
+    /// Expects non-negative weights, `None` when `2 * offset` doesn't fit
+    /// into `W`. The diagonal of `atsp` is ignored.
+    pub fn new(atsp: &Tsp<W>) -> Option<SymmetricTransform<W>> {
+        let dimension = atsp.get_dimension();
+        let offset = SymmetricTransform::offset(atsp)?;
+
+        if offset > W::MAX - offset {
+            return None;
+        }
+
+        let forbidden = offset + offset;
+
+        let weights = DenseMatrix::from_fn(2 * dimension, |from, to| {
+            let (original, twin) = match (from < dimension, to < dimension) {
+                (true, false) => (from, to - dimension),
+                (false, true) => (to, from - dimension),
+                _ if from == to => return W::ZERO,
+                _ => return forbidden,
+            };
+
+            if original == twin {
+                W::ZERO
+            } else {
+                atsp.get_edge(twin, original) + offset
+            }
+        });
+
+        let header = TspHeader {
+            name: atsp.get_header().name.clone(),
+            problem_type: Some(ProblemType::Tsp),
+            comment: atsp.get_header().comment.clone(),
+            dimension: 2 * dimension,
+            ..TspHeader::default()
+        };
+
+        let tsp = Tsp::new(
+            Distances::Matrix(weights),
+            2 * dimension,
+            TspType::Symmetric,
+        )
+        .with_header(header)
+        .with_fixed_edges(
+            atsp.get_fixed_edges()
+                .iter()
+                .map(|&(from, to)| (from + dimension, to))
+                .collect(),
+        )
+        .expect("every node keeps at most one fixed edge");
+
+        Some(SymmetricTransform {
+            tsp,
+            dimension,
+            offset,
+        })
+    }
+
+    // one more than the sum of the heaviest arc leaving every node
+    fn offset(atsp: &Tsp<W>) -> Option<W> {
+        let dimension = atsp.get_dimension();
+
+        let mut offset = W::ONE;
+
+        for from in 0..dimension {
+            let mut heaviest = W::ZERO;
+
+            for to in (0..dimension).filter(|&to| to != from) {
+                if atsp.get_edge(from, to) > heaviest {
+                    heaviest = atsp.get_edge(from, to);
+                }
+            }
+
+            if offset > W::MAX - heaviest {
+                return None;
+            }
+
+            offset = offset + heaviest;
+        }
+
+        Some(offset)
+    }
+
+    pub fn get_tsp(&self) -> &Tsp<W> {
+        &self.tsp
+    }
+
+    /// Weight added to every arc, a symmetric route alternating between
+    /// nodes and their twins is longer by `dimension * offset`.
+    pub fn get_offset(&self) -> W {
+        self.offset
+    }
+
+    /// Visits every node followed by its twin.
+    pub fn to_symmetric_route(&self, route: &[usize]) -> Vec<usize> {
+        route
+            .iter()
+            .flat_map(|&node| [node, node + self.dimension])
+            .collect()
+    }
+
+    /// Original nodes in the order of the symmetric route, read in the
+    /// direction where nodes are followed by their twins. Lengths of both
+    /// routes differ by `dimension * offset` only if the route alternates,
+    /// which every route shorter than `(dimension + 1) * offset` does.
+    pub fn to_asymmetric_route(&self, route: &[usize]) -> Vec<usize> {
+        let len = route.len();
+
+        let Some(start) = route.iter().position(|&node| node == 0) else {
+            return Vec::new();
+        };
+
+        let is_forward = route[(start + 1) % len] == self.dimension;
+
+        (0..len)
+            .map(|i| {
+                if is_forward {
+                    route[(start + i) % len]
+                } else {
+                    route[(start + len - i) % len]
+                }
+            })
+            .filter(|&node| node < self.dimension)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NearestNeighbour, TspHeuristic, TspParser, TwoOpt};
+
+    #[test]
+    fn routes_keep_their_length() {
+        let atsp = TspParser::from_file("test_files/br17.atsp").expect("Couldn't parse file");
+        let transform = SymmetricTransform::new(&atsp).expect("weights are small");
+
+        let shift = 17 * u64::from(transform.get_offset());
+        let mut route = NearestNeighbour::new().get_route(&atsp);
+        let start = route.iter().position(|&node| node == 0).unwrap();
+        route.rotate_left(start);
+
+        let symmetric_route = transform.to_symmetric_route(&route);
+
+        assert_eq!(
+            atsp.get_route_len(&route).unwrap() + shift,
+            transform.get_tsp().get_route_len(&symmetric_route).unwrap()
+        );
+
+        let mut reversed = symmetric_route.clone();
+        reversed.reverse();
+        reversed.rotate_left(5);
+
+        assert_eq!(route, transform.to_asymmetric_route(&symmetric_route));
+        assert_eq!(route, transform.to_asymmetric_route(&reversed));
+    }
+
+    #[test]
+    fn symmetric_heuristics_solve_atsp() {
+        for filename in [
+            "test_files/br17.atsp",
+            "test_files/ftv33.atsp",
+            "test_files/ft70.atsp",
+        ] {
+            let atsp = TspParser::from_file(filename).expect("Couldn't parse file");
+            let transform = SymmetricTransform::new(&atsp).expect("weights are small");
+            let tsp = transform.get_tsp();
+
+            assert!(tsp.analyze().symmetric);
+
+            let symmetric_route = TwoOpt::new(NearestNeighbour::new()).get_route(tsp);
+            let symmetric_len = tsp.get_route_len(&symmetric_route).unwrap();
+
+            let route = transform.to_asymmetric_route(&symmetric_route);
+            let shift = atsp.get_dimension() as u64 * u64::from(transform.get_offset());
+
+            assert_eq!(
+                symmetric_len,
+                atsp.get_route_len(&route).unwrap() + shift,
+                "{}",
+                filename
+            );
+        }
+    }
+
+    #[test]
+    fn fixed_arcs_stay_fixed() {
+        let atsp = TspParser::from_file("test_files/ft70.atsp")
+            .expect("Couldn't parse file")
+            .with_fixed_edges(vec![(0, 69), (69, 10), (30, 20), (7, 8)])
+            .expect("fixed edges have to be valid");
+        let transform = SymmetricTransform::new(&atsp).expect("weights are small");
+        let tsp = transform.get_tsp();
+
+        assert_eq!(
+            &[(70, 69), (139, 10), (100, 20), (77, 8)],
+            tsp.get_fixed_edges()
+        );
+
+        let symmetric_route = TwoOpt::new(NearestNeighbour::new()).get_route(tsp);
+        let route = transform.to_asymmetric_route(&symmetric_route);
+
+        atsp.check_route_valid(&route)
+            .expect("fixed edges have to be kept");
+    }
+
+    #[test]
+    fn offset_has_to_fit() {
+        let atsp: Tsp = Tsp::new(
+            Distances::Matrix(DenseMatrix::from_rows(vec![
+                vec![0, u32::MAX / 3],
+                vec![u32::MAX / 3, 0],
+            ])),
+            2,
+            TspType::Asymmetric,
+        );
+
+        assert!(SymmetricTransform::new(&atsp).is_none());
+    }
+}
@@ -0,0 +1,346 @@
+use crate::distances::Distances;
+use crate::kd_tree::KdTree;
+use crate::tsp::Tsp;
+use crate::weight::Weight;
+
+/// Candidates per city of lists built when none were chosen.
+pub const DEFAULT_CANDIDATES: usize = 8;
+
+/// How the candidate lists of a `Tsp` are made, see `Tsp::get_candidates`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CandidateSource {
+    /// Given number of cities with the lightest edges from every city.
+    Nearest(usize),
+    /// Nearest cities from each quadrant around every city, so candidates
+    /// don't all lie on one side of clustered cities. Filled up by the
+    /// nearest ones to the given number of cities. Instances without node
+    /// coordinates fall back to `Nearest`.
+    Quadrant(usize),
+    /// Lists given by the user, one for every city. Cities out of range,
+    /// the city itself and repeated cities are dropped, cities without a
+    /// list get an empty one and extra lists are ignored.
+    Given(Vec<Vec<usize>>),
+}
+
+impl CandidateSource {
+    /// Whether lists made for `tsp` hold the cities with the lightest edges
+    /// from every city, known without making them.
+    pub fn is_nearest<W: Weight>(&self, tsp: &Tsp<W>) -> bool {
+        match self {
+            CandidateSource::Nearest(_) => true,
+            CandidateSource::Quadrant(_) => {
+                !matches!(tsp.get_distances(), Distances::Coordinates(..))
+            }
+            CandidateSource::Given(_) => false,
+        }
+    }
+}
+
+/// Cities heuristics try new edges to, sorted by weight of the edge from
+/// the city for every city.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CandidateLists {
+    lists: Vec<Vec<usize>>,
+    is_nearest: bool,
+}
+
+impl CandidateLists {
+    pub fn new<W: Weight>(tsp: &Tsp<W>, source: &CandidateSource) -> CandidateLists {
+        let dimension = tsp.get_dimension();
+        let is_nearest = source.is_nearest(tsp);
+
+        let lists = match source {
+            CandidateSource::Nearest(count) => match KdTree::from_tsp(tsp) {
+                Some(tree) => (0..dimension)
+                    .map(|from| {
+                        let list = tree.nearest_k(tree.get_point(from), *count, |to| to != from);
+
+                        sorted_by_edge(tsp, from, list)
+                    })
+                    .collect(),
+                None => nearest(tsp, *count),
+            },
+            CandidateSource::Quadrant(count) => {
+                match (KdTree::from_tsp(tsp), tsp.get_distances()) {
+                    (Some(tree), _) => tree_quadrant(tsp, &tree, *count),
+                    (None, Distances::Coordinates(coords, _)) => quadrant(tsp, coords, *count),
+                    _ => nearest(tsp, *count),
+                }
+            }
+            CandidateSource::Given(lists) => (0..dimension)
+                .map(|from| {
+                    let list = lists.get(from).map_or(&[][..], Vec::as_slice);
+
+                    sorted_by_edge(tsp, from, valid_candidates(dimension, from, list))
+                })
+                .collect(),
+        };
+
+        CandidateLists { lists, is_nearest }
+    }
+
+    /// Whether every list holds the cities with the lightest edges from its
+    /// city, so no city left out of a list is nearer than its last one.
+    pub fn is_nearest(&self) -> bool {
+        self.is_nearest
+    }
+
+    pub fn get(&self, vertex: usize) -> &[usize] {
+        &self.lists[vertex]
+    }
+
+    pub fn get_lists(&self) -> &[Vec<usize>] {
+        &self.lists
+    }
+}
+
+fn nearest<W: Weight>(tsp: &Tsp<W>, count: usize) -> Vec<Vec<usize>> {
+    (0..tsp.get_dimension())
+        .map(|from| {
+            let weights = weights_from(tsp, from);
+            let others = (0..tsp.get_dimension()).filter(|&to| to != from).collect();
+
+            lightest(&weights, others, count)
+        })
+        .collect()
+}
+
+fn quadrant<W: Weight>(tsp: &Tsp<W>, coords: &[(f64, f64)], count: usize) -> Vec<Vec<usize>> {
+    (0..tsp.get_dimension())
+        .map(|from| {
+            let weights = weights_from(tsp, from);
+            let mut quadrants = [(); 4].map(|_| Vec::new());
+
+            for to in (0..tsp.get_dimension()).filter(|&to| to != from) {
+                quadrants[quadrant_of(coords[from], coords[to])].push(to);
+            }
+
+            let mut list: Vec<_> = quadrants
+                .into_iter()
+                .flat_map(|cities| lightest(&weights, cities, count / 4))
+                .collect();
+
+            let others = (0..tsp.get_dimension()).filter(|&to| to != from).collect();
+
+            // the nearest cities fill up the list
+            for to in lightest(&weights, others, count) {
+                if list.len() < count && !list.contains(&to) {
+                    list.push(to);
+                }
+            }
+
+            sorted_by_weight(&weights, list)
+        })
+        .collect()
+}
+
+// quadrants are found by the tree, which also fills up the list
+fn tree_quadrant<W: Weight>(tsp: &Tsp<W>, tree: &KdTree, count: usize) -> Vec<Vec<usize>> {
+    (0..tsp.get_dimension())
+        .map(|from| {
+            let point = tree.get_point(from);
+
+            let mut list: Vec<_> = (0..4)
+                .flat_map(|quadrant| {
+                    tree.nearest_k(point, count / 4, |to| {
+                        to != from && quadrant_of(point, tree.get_point(to)) == quadrant
+                    })
+                })
+                .collect();
+
+            for to in tree.nearest_k(point, count, |to| to != from) {
+                if list.len() < count && !list.contains(&to) {
+                    list.push(to);
+                }
+            }
+
+            sorted_by_edge(tsp, from, list)
+        })
+        .collect()
+}
+
+fn valid_candidates(dimension: usize, from: usize, list: &[usize]) -> Vec<usize> {
+    let mut valid = Vec::with_capacity(list.len());
+
+    for &to in list {
+        if to < dimension && to != from && !valid.contains(&to) {
+            valid.push(to);
+        }
+    }
+
+    valid
+}
+
+fn quadrant_of((x, y): (f64, f64), (to_x, to_y): (f64, f64)) -> usize {
+    match (to_x >= x, to_y >= y) {
+        (true, true) => 0,
+        (false, true) => 1,
+        (false, false) => 2,
+        (true, false) => 3,
+    }
+}
+
+fn weights_from<W: Weight>(tsp: &Tsp<W>, from: usize) -> Vec<W> {
+    (0..tsp.get_dimension())
+        .map(|to| tsp.get_edge(from, to))
+        .collect()
+}
+
+// `count` of `cities` with the lightest `weights`, sorted
+fn lightest<W: Weight>(weights: &[W], mut cities: Vec<usize>, count: usize) -> Vec<usize> {
+    if count < cities.len() {
+        cities.select_nth_unstable_by(count, |&first, &second| {
+            weights[first]
+                .partial_cmp(&weights[second])
+                .expect("weights are comparable")
+        });
+        cities.truncate(count);
+    }
+
+    sorted_by_weight(weights, cities)
+}
+
+fn sorted_by_weight<W: Weight>(weights: &[W], mut cities: Vec<usize>) -> Vec<usize> {
+    cities.sort_by(|&first, &second| {
+        weights[first]
+            .partial_cmp(&weights[second])
+            .expect("weights are comparable")
+    });
+
+    cities
+}
+
+// stable, so cities with the same weight stay in the order given
+fn sorted_by_edge<W: Weight>(tsp: &Tsp<W>, from: usize, mut cities: Vec<usize>) -> Vec<usize> {
+    cities.sort_by(|&first, &second| {
+        tsp.get_edge(from, first)
+            .partial_cmp(&tsp.get_edge(from, second))
+            .expect("weights are comparable")
+    });
+
+    cities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TspParser;
+
+    #[test]
+    fn nearest_candidates_are_sorted() {
+        let tsp = TspParser::from_file("test_files/berlin52.tsp").expect("Couldn't parse file");
+
+        let candidates = CandidateLists::new(&tsp, &CandidateSource::Nearest(5));
+
+        for from in 0..tsp.get_dimension() {
+            let list = candidates.get(from);
+            let farthest = tsp.get_edge(from, list[4]);
+
+            assert_eq!(5, list.len());
+            assert!(!list.contains(&from));
+            assert!(list
+                .windows(2)
+                .all(|pair| tsp.get_edge(from, pair[0]) <= tsp.get_edge(from, pair[1])));
+            assert!((0..tsp.get_dimension())
+                .filter(|&to| to != from && !list.contains(&to))
+                .all(|to| tsp.get_edge(from, to) >= farthest));
+        }
+    }
+
+    #[test]
+    fn quadrant_candidates_surround_city() {
+        let tsp = TspParser::from_file("test_files/berlin52.tsp").expect("Couldn't parse file");
+        let coords = tsp.coordinates().expect("has coordinates");
+
+        let candidates = CandidateLists::new(&tsp, &CandidateSource::Quadrant(8));
+
+        for from in 0..tsp.get_dimension() {
+            let (x, y) = coords[from];
+            let list = candidates.get(from);
+
+            assert_eq!(8, list.len());
+
+            if (0..tsp.get_dimension()).any(|to| coords[to].0 < x && coords[to].1 < y) {
+                assert!(list.iter().any(|&to| coords[to].0 < x && coords[to].1 < y));
+            }
+        }
+    }
+
+    #[test]
+    fn tree_candidates_match_scans() {
+        let tsp = TspParser::from_file("test_files/pr76.tsp").expect("Couldn't parse file");
+        let coords = tsp.coordinates().unwrap();
+
+        let weights = |lists: &[Vec<usize>]| -> Vec<Vec<u32>> {
+            lists
+                .iter()
+                .enumerate()
+                .map(|(from, list)| list.iter().map(|&to| tsp.get_edge(from, to)).collect())
+                .collect()
+        };
+
+        let candidates = CandidateLists::new(&tsp, &CandidateSource::Nearest(6));
+        assert_eq!(weights(&nearest(&tsp, 6)), weights(candidates.get_lists()));
+
+        let candidates = CandidateLists::new(&tsp, &CandidateSource::Quadrant(8));
+        assert_eq!(
+            weights(&quadrant(&tsp, coords, 8)),
+            weights(candidates.get_lists())
+        );
+    }
+
+    #[test]
+    fn given_candidates_get_sorted() {
+        let tsp = TspParser::from_file("test_files/ulysses16.tsp").expect("Couldn't parse file");
+
+        let list = vec![15, 1, 7];
+        let candidates = CandidateLists::new(&tsp, &CandidateSource::Given(vec![list.clone(); 16]));
+
+        let mut sorted = list;
+        sorted.sort_by_key(|&to| tsp.get_edge(0, to));
+
+        assert_eq!(sorted, candidates.get(0));
+    }
+
+    #[test]
+    fn given_candidates_get_validated() {
+        let tsp = TspParser::from_file("test_files/ulysses16.tsp").expect("Couldn't parse file");
+
+        let candidates = |lists: Vec<Vec<usize>>| {
+            CandidateLists::new(&tsp, &CandidateSource::Given(lists))
+                .get_lists()
+                .to_vec()
+        };
+
+        // self-loops
+        assert_eq!(vec![1], candidates(vec![vec![0, 1]; 16])[0]);
+        // out of range cities
+        assert_eq!(vec![1], candidates(vec![vec![16, 1, 100]; 16])[0]);
+        // repeated cities
+        assert_eq!(vec![1], candidates(vec![vec![1, 1]; 16])[0]);
+
+        // too few lists
+        let lists = candidates(vec![vec![1]; 3]);
+
+        assert_eq!(16, lists.len());
+        assert!(lists[3..].iter().all(Vec::is_empty));
+
+        // too many lists
+        assert_eq!(16, candidates(vec![vec![1]; 20]).len());
+    }
+
+    #[test]
+    fn tsp_caches_candidates() {
+        let tsp = TspParser::from_file("test_files/berlin52.tsp").expect("Couldn't parse file");
+
+        assert!(std::ptr::eq(tsp.get_candidates(), tsp.get_candidates()));
+        assert_eq!(DEFAULT_CANDIDATES, tsp.get_candidates().get(0).len());
+
+        let tsp = tsp.with_candidates(CandidateSource::Nearest(3));
+
+        assert_eq!(
+            &CandidateLists::new(&tsp, &CandidateSource::Nearest(3)),
+            tsp.get_candidates()
+        );
+    }
+}
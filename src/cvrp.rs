@@ -0,0 +1,328 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::str::FromStr;
+
+use crate::tsp::Tsp;
+use crate::tsp_parser::{TspParser, TspParsingError};
+use crate::weight::Weight;
+
+mod clarke_wright;
+mod local_search;
+
+pub use clarke_wright::ClarkeWright;
+pub use local_search::CvrpLocalSearch;
+
+#[derive(Debug)]
+pub enum CvrpRouteError {
+    NotPartition,
+    DepotInRoute,
+    OverCapacity { route: usize, demand: u64 },
+}
+
+impl std::fmt::Display for CvrpRouteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CvrpRouteError::NotPartition => {
+                write!(f, "routes don't visit every customer exactly once")
+            }
+            CvrpRouteError::DepotInRoute => write!(f, "route visits the depot in between"),
+            CvrpRouteError::OverCapacity { route, demand } => {
+                write!(f, "route {} carries {} over the capacity", route, demand)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CvrpRouteError {}
+
+/// Routes of a CVRP solution. Every route leaves the depot, visits its
+/// customers in order and comes back, so the depot itself isn't stored.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CvrpSolution {
+    routes: Vec<Vec<usize>>,
+}
+
+impl CvrpSolution {
+    pub fn new(routes: Vec<Vec<usize>>) -> CvrpSolution {
+        CvrpSolution { routes }
+    }
+
+    pub fn get_routes(&self) -> &[Vec<usize>] {
+        &self.routes
+    }
+
+    pub fn into_routes(self) -> Vec<Vec<usize>> {
+        self.routes
+    }
+}
+
+/// Capacitated vehicle routing problem, distances come from the underlying
+/// `Tsp` and the depot is one of its vertices. Lengths are summed in
+/// `W::Length` and demands in `u64`, so neither can overflow. `from_*`
+/// functions are shorthands for the default `u32` weights.
+#[derive(Debug, Clone)]
+pub struct Cvrp<W = u32> {
+    tsp: Tsp<W>,
+    capacity: u32,
+    demands: Vec<u32>,
+    depot: usize,
+}
+
+impl Cvrp {
+    pub fn from_file(filename: &str) -> Result<Cvrp, TspParsingError> {
+        Cvrp::parse_file(filename)
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Cvrp, TspParsingError> {
+        Cvrp::parse_reader(reader)
+    }
+}
+
+impl<W: Weight> Cvrp<W> {
+    pub fn new(tsp: Tsp<W>, capacity: u32, demands: Vec<u32>, depot: usize) -> Cvrp<W> {
+        Cvrp {
+            tsp,
+            capacity,
+            demands,
+            depot,
+        }
+    }
+
+    pub fn parse_file(filename: &str) -> Result<Cvrp<W>, TspParsingError> {
+        let file = File::open(filename).map_err(TspParsingError::FileDoesntExist)?;
+
+        Cvrp::parse_reader(BufReader::new(file))
+    }
+
+    /// Only the first node of DEPOT_SECTION is used as the depot.
+    pub fn parse_reader<R: BufRead>(reader: R) -> Result<Cvrp<W>, TspParsingError> {
+        let (tsp, extra_sections) = TspParser::parse_with_extra_sections(reader)?;
+
+        let capacity = tsp
+            .get_header()
+            .capacity
+            .ok_or(TspParsingError::NoCapacity)?;
+        let demands = extra_sections.demands.ok_or(TspParsingError::NoDemands)?;
+        let depot = *extra_sections
+            .depots
+            .first()
+            .ok_or(TspParsingError::NoDepot)?;
+
+        Ok(Cvrp::new(tsp, capacity, demands, depot))
+    }
+
+    pub fn get_solution_len(&self, solution: &CvrpSolution) -> Result<W::Length, CvrpRouteError> {
+        self.check_solution_valid(solution)?;
+
+        let mut solution_len = W::Length::default();
+
+        for route in &solution.routes {
+            solution_len += self.get_route_len(route);
+        }
+
+        Ok(solution_len)
+    }
+
+    /// Length of a single route including the way from and back to the depot.
+    pub fn get_route_len(&self, route: &[usize]) -> W::Length {
+        let mut route_len = W::Length::default();
+
+        if route.is_empty() {
+            return route_len;
+        }
+
+        route_len += self.get_edge(self.depot, route[0]).into();
+
+        for i in 0..route.len() - 1 {
+            route_len += self.get_edge(route[i], route[i + 1]).into();
+        }
+
+        route_len + self.get_edge(route[route.len() - 1], self.depot).into()
+    }
+
+    pub fn get_route_demand(&self, route: &[usize]) -> u64 {
+        route
+            .iter()
+            .map(|&customer| u64::from(self.demands[customer]))
+            .sum()
+    }
+
+    pub fn check_solution_valid(&self, solution: &CvrpSolution) -> Result<(), CvrpRouteError> {
+        let mut visited = vec![false; self.get_dimension()];
+
+        for (i, route) in solution.routes.iter().enumerate() {
+            for &customer in route {
+                if customer == self.depot {
+                    return Err(CvrpRouteError::DepotInRoute);
+                }
+
+                if customer >= visited.len() || visited[customer] {
+                    return Err(CvrpRouteError::NotPartition);
+                }
+
+                visited[customer] = true;
+            }
+
+            let demand = self.get_route_demand(route);
+
+            if demand > u64::from(self.capacity) {
+                return Err(CvrpRouteError::OverCapacity { route: i, demand });
+            }
+        }
+
+        if self.customers().any(|customer| !visited[customer]) {
+            return Err(CvrpRouteError::NotPartition);
+        }
+
+        Ok(())
+    }
+
+    pub fn customers(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.get_dimension()).filter(move |&vertex| vertex != self.depot)
+    }
+
+    #[inline]
+    pub fn get_edge(&self, from: usize, to: usize) -> W {
+        self.tsp.get_edge(from, to)
+    }
+
+    pub fn get_tsp(&self) -> &Tsp<W> {
+        &self.tsp
+    }
+
+    pub fn get_dimension(&self) -> usize {
+        self.tsp.get_dimension()
+    }
+
+    pub fn get_capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    pub fn get_demands(&self) -> &[u32] {
+        &self.demands
+    }
+
+    pub fn get_depot(&self) -> usize {
+        self.depot
+    }
+}
+
+impl<W: Weight> FromStr for Cvrp<W> {
+    type Err = TspParsingError;
+
+    fn from_str(content: &str) -> Result<Cvrp<W>, TspParsingError> {
+        Cvrp::parse_reader(content.as_bytes())
+    }
+}
+
+pub trait CvrpHeuristic<W: Weight = u32> {
+    fn get_solution(&self, cvrp: &Cvrp<W>) -> CvrpSolution;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DenseMatrix, Distances, TspType};
+
+    #[test]
+    fn cvrp_file_works() {
+        let cvrp = Cvrp::from_file("cvrp").expect("Couldn't parse file");
+
+        assert_eq!(9, cvrp.get_dimension());
+        assert_eq!(10, cvrp.get_capacity());
+        assert_eq!(0, cvrp.get_depot());
+        assert_eq!(&[0, 4, 3, 5, 2, 6, 3, 4, 3], cvrp.get_demands());
+    }
+
+    #[test]
+    fn solution_len_works() {
+        let cvrp = Cvrp::from_file("cvrp").expect("Couldn't parse file");
+        let solution = CvrpSolution::new(vec![vec![1, 2], vec![3, 4], vec![5, 6], vec![7, 8]]);
+
+        assert_eq!(435, cvrp.get_solution_len(&solution).unwrap());
+    }
+
+    #[test]
+    fn solution_len_doesnt_overflow() {
+        let tsp = Tsp::new(
+            Distances::Matrix(DenseMatrix::from_fn(3, |from, to| {
+                if from == to {
+                    0
+                } else {
+                    u32::MAX
+                }
+            })),
+            3,
+            TspType::Symmetric,
+        );
+        let cvrp = Cvrp::new(tsp, u32::MAX, vec![0, u32::MAX, u32::MAX], 0);
+
+        let solution = CvrpSolution::new(vec![vec![1], vec![2]]);
+        let merged = CvrpSolution::new(vec![vec![1, 2]]);
+
+        assert_eq!(
+            4 * u64::from(u32::MAX),
+            cvrp.get_solution_len(&solution).unwrap()
+        );
+        assert!(matches!(
+            cvrp.check_solution_valid(&merged),
+            Err(CvrpRouteError::OverCapacity { route: 0, demand })
+                if demand == 2 * u64::from(u32::MAX)
+        ));
+    }
+
+    #[test]
+    fn other_weights_work() {
+        let content = std::fs::read_to_string("cvrp").expect("file doesn't exist");
+        let cvrp = content.parse::<Cvrp<f64>>().expect("Couldn't parse file");
+        let solution = CvrpSolution::new(vec![vec![1, 2], vec![3, 4], vec![5, 6], vec![7, 8]]);
+
+        assert_eq!(435.0, cvrp.get_solution_len(&solution).unwrap());
+    }
+
+    #[test]
+    fn invalid_solutions() {
+        let cvrp = Cvrp::from_file("cvrp").expect("Couldn't parse file");
+
+        let over_capacity = CvrpSolution::new(vec![vec![1, 2, 3], vec![4, 5, 6, 7, 8]]);
+        let missing_customer = CvrpSolution::new(vec![vec![1, 2], vec![3, 4], vec![5, 6], vec![7]]);
+        let with_depot = CvrpSolution::new(vec![vec![1, 2, 0], vec![3, 4], vec![5, 6], vec![7, 8]]);
+
+        assert!(matches!(
+            cvrp.check_solution_valid(&over_capacity),
+            Err(CvrpRouteError::OverCapacity {
+                route: 0,
+                demand: 12
+            })
+        ));
+        assert!(matches!(
+            cvrp.check_solution_valid(&missing_customer),
+            Err(CvrpRouteError::NotPartition)
+        ));
+        assert!(matches!(
+            cvrp.check_solution_valid(&with_depot),
+            Err(CvrpRouteError::DepotInRoute)
+        ));
+    }
+
+    #[test]
+    fn missing_cvrp_sections() {
+        let content = std::fs::read_to_string("cvrp").expect("file doesn't exist");
+
+        let without_depot = content.replace("DEPOT_SECTION\n1\n-1\n", "");
+        let without_capacity = content.replace("CAPACITY: 10\n", "");
+
+        assert!(matches!(
+            without_depot.parse::<Cvrp>(),
+            Err(TspParsingError::NoDepot)
+        ));
+        assert!(matches!(
+            without_capacity.parse::<Cvrp>(),
+            Err(TspParsingError::NoCapacity)
+        ));
+        assert!(matches!(
+            Cvrp::from_file("euc_2d"),
+            Err(TspParsingError::NoCapacity)
+        ));
+    }
+}
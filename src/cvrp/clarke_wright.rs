@@ -0,0 +1,132 @@
+use crate::{Cvrp, CvrpHeuristic, CvrpSolution, TspType, Weight};
+
+/// Parallel savings algorithm. Starts with a route per customer and merges
+/// the pair of routes with the biggest saving while capacity allows it.
+///
+/// Symmetric instances may join routes at either end, asymmetric ones only
+/// the end of one route with the start of another.
+#[derive(Default)]
+pub struct ClarkeWright;
+
+impl ClarkeWright {
+    pub fn new() -> ClarkeWright {
+        ClarkeWright
+    }
+}
+
+impl<W: Weight> CvrpHeuristic<W> for ClarkeWright {
+    fn get_solution(&self, cvrp: &Cvrp<W>) -> CvrpSolution {
+        let depot = cvrp.get_depot();
+        let is_symmetric = matches!(cvrp.get_tsp().get_tsp_type(), TspType::Symmetric);
+
+        let mut routes = (0..cvrp.get_dimension())
+            .map(|vertex| {
+                if vertex == depot {
+                    vec![]
+                } else {
+                    vec![vertex]
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut route_of = (0..cvrp.get_dimension()).collect::<Vec<_>>();
+        let mut route_demands = cvrp
+            .get_demands()
+            .iter()
+            .map(|&demand| u64::from(demand))
+            .collect::<Vec<_>>();
+
+        let mut savings = Vec::new();
+
+        for i in cvrp.customers() {
+            for j in cvrp.customers() {
+                if i == j || (is_symmetric && j < i) {
+                    continue;
+                }
+
+                let mut gain = W::Length::from(cvrp.get_edge(i, depot));
+                gain += cvrp.get_edge(depot, j).into();
+                let cost = W::Length::from(cvrp.get_edge(i, j));
+
+                // lengths may be unsigned, so compare before subtracting
+                if gain > cost {
+                    savings.push((gain - cost, i, j));
+                }
+            }
+        }
+
+        savings.sort_unstable_by(|(saving_a, i_a, j_a), (saving_b, i_b, j_b)| {
+            saving_b
+                .partial_cmp(saving_a)
+                .expect("weights are comparable")
+                .then((i_a, j_a).cmp(&(i_b, j_b)))
+        });
+
+        for (_, i, j) in savings {
+            let (first, second) = (route_of[i], route_of[j]);
+
+            if first == second
+                || route_demands[first] + route_demands[second] > u64::from(cvrp.get_capacity())
+            {
+                continue;
+            }
+
+            // i has to end the first route and j has to start the second one
+            let i_at_end = routes[first].last() == Some(&i);
+            let j_at_start = routes[second][0] == j;
+
+            let can_merge = (i_at_end || (is_symmetric && routes[first][0] == i))
+                && (j_at_start || (is_symmetric && routes[second].last() == Some(&j)));
+
+            if !can_merge {
+                continue;
+            }
+
+            if !i_at_end {
+                routes[first].reverse();
+            }
+
+            if !j_at_start {
+                routes[second].reverse();
+            }
+
+            let second_route = std::mem::take(&mut routes[second]);
+
+            for &customer in &second_route {
+                route_of[customer] = first;
+            }
+
+            routes[first].extend(second_route);
+            route_demands[first] += route_demands[second];
+        }
+
+        CvrpSolution::new(
+            routes
+                .into_iter()
+                .filter(|route| !route.is_empty())
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clarke_wright_works() {
+        let cvrp = Cvrp::from_file("cvrp").expect("Couldn't parse file");
+
+        let solution = ClarkeWright::new().get_solution(&cvrp);
+        let solution_len = cvrp
+            .get_solution_len(&solution)
+            .expect("Has to be valid solution");
+
+        let single_routes = cvrp
+            .customers()
+            .map(|customer| cvrp.get_route_len(&[customer]))
+            .sum::<u64>();
+
+        assert!(solution_len >= 435);
+        assert!(solution_len < single_routes);
+    }
+}
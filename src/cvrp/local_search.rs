@@ -0,0 +1,360 @@
+use crate::{Cvrp, CvrpHeuristic, CvrpSolution, RouteLength, TspType, Weight};
+
+/// Improves routes of the initial heuristic with the best of intra-route
+/// 2-opt, relocate (within and between routes) and inter-route 2-opt*, which
+/// exchanges route tails, until none of them shortens the solution.
+pub struct CvrpLocalSearch<H> {
+    initial_heuristic: H,
+}
+
+impl<H> CvrpLocalSearch<H> {
+    pub fn new(initial_heuristic: H) -> Self {
+        CvrpLocalSearch { initial_heuristic }
+    }
+}
+
+impl<W, H> CvrpHeuristic<W> for CvrpLocalSearch<H>
+where
+    W: Weight,
+    H: CvrpHeuristic<W>,
+{
+    fn get_solution(&self, cvrp: &Cvrp<W>) -> CvrpSolution {
+        let mut routes = self.initial_heuristic.get_solution(cvrp).into_routes();
+
+        while let Some((_, best_move)) = [
+            best_two_opt(cvrp, &routes),
+            best_relocate(cvrp, &routes),
+            best_two_opt_star(cvrp, &routes),
+        ]
+        .into_iter()
+        .flatten()
+        .reduce(|best, other| if other.0 > best.0 { other } else { best })
+        {
+            best_move.apply(&mut routes);
+            routes.retain(|route| !route.is_empty());
+        }
+
+        CvrpSolution::new(routes)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Move {
+    // inverts route[i..=j]
+    TwoOpt {
+        route: usize,
+        i: usize,
+        j: usize,
+    },
+    // removes from[i] and inserts it at index j of `to` without that customer
+    Relocate {
+        from: usize,
+        i: usize,
+        to: usize,
+        j: usize,
+    },
+    // first keeps its first i customers, second its first j, tails are swapped
+    TwoOptStar {
+        first: usize,
+        i: usize,
+        second: usize,
+        j: usize,
+    },
+}
+
+impl Move {
+    fn apply(self, routes: &mut [Vec<usize>]) {
+        match self {
+            Move::TwoOpt { route, i, j } => routes[route][i..=j].reverse(),
+            Move::Relocate { from, i, to, j } => {
+                let customer = routes[from].remove(i);
+                routes[to].insert(j, customer);
+            }
+            Move::TwoOptStar {
+                first,
+                i,
+                second,
+                j,
+            } => {
+                let first_tail = routes[first].split_off(i);
+                let second_tail = routes[second].split_off(j);
+
+                routes[first].extend(second_tail);
+                routes[second].extend(first_tail);
+            }
+        }
+    }
+}
+
+// vertex at position `position` of the route surrounded by the depot
+fn at<W: Weight>(cvrp: &Cvrp<W>, route: &[usize], position: usize) -> usize {
+    if position == 0 || position > route.len() {
+        cvrp.get_depot()
+    } else {
+        route[position - 1]
+    }
+}
+
+fn edge<W: Weight>(cvrp: &Cvrp<W>, from: usize, to: usize) -> W::Length {
+    cvrp.get_edge(from, to).into()
+}
+
+// keeps the move if it shortens the solution more than the best one so far,
+// lengths may be unsigned, so the improvement is tracked instead of a delta
+fn keep_better<L: RouteLength>(best: &mut Option<(L, Move)>, removed: L, added: L, new_move: Move) {
+    if added >= removed {
+        return;
+    }
+
+    let improvement = removed - added;
+
+    if best.is_none_or(|(best_improvement, _)| improvement > best_improvement) {
+        *best = Some((improvement, new_move));
+    }
+}
+
+fn best_two_opt<W: Weight>(cvrp: &Cvrp<W>, routes: &[Vec<usize>]) -> Option<(W::Length, Move)> {
+    let is_symmetric = matches!(cvrp.get_tsp().get_tsp_type(), TspType::Symmetric);
+    let mut best = None;
+
+    for (r, route) in routes.iter().enumerate() {
+        for i in 0..route.len() {
+            // reversed inner edges of the segment, only matters when asymmetric
+            let mut inner_removed = W::Length::default();
+            let mut inner_added = W::Length::default();
+
+            for j in i + 1..route.len() {
+                if !is_symmetric {
+                    inner_removed += edge(cvrp, route[j - 1], route[j]);
+                    inner_added += edge(cvrp, route[j], route[j - 1]);
+                }
+
+                let (before, after) = (at(cvrp, route, i), at(cvrp, route, j + 2));
+
+                let removed =
+                    edge(cvrp, before, route[i]) + edge(cvrp, route[j], after) + inner_removed;
+                let added =
+                    edge(cvrp, before, route[j]) + edge(cvrp, route[i], after) + inner_added;
+
+                keep_better(&mut best, removed, added, Move::TwoOpt { route: r, i, j });
+            }
+        }
+    }
+
+    best
+}
+
+fn best_relocate<W: Weight>(cvrp: &Cvrp<W>, routes: &[Vec<usize>]) -> Option<(W::Length, Move)> {
+    let route_demands = routes
+        .iter()
+        .map(|route| cvrp.get_route_demand(route))
+        .collect::<Vec<_>>();
+
+    let mut best = None;
+
+    for (from, from_route) in routes.iter().enumerate() {
+        for i in 0..from_route.len() {
+            let customer = from_route[i];
+            let (before, after) = (at(cvrp, from_route, i), at(cvrp, from_route, i + 2));
+
+            let remove_removed = edge(cvrp, before, customer) + edge(cvrp, customer, after);
+            let remove_added = edge(cvrp, before, after);
+
+            for (to, to_route) in routes.iter().enumerate() {
+                if from == to {
+                    let mut without_customer = from_route.clone();
+                    without_customer.remove(i);
+
+                    for j in (0..from_route.len()).filter(|&j| j != i) {
+                        let mut moved = without_customer.clone();
+                        moved.insert(j, customer);
+
+                        keep_better(
+                            &mut best,
+                            cvrp.get_route_len(from_route),
+                            cvrp.get_route_len(&moved),
+                            Move::Relocate { from, i, to, j },
+                        );
+                    }
+
+                    continue;
+                }
+
+                let demand = u64::from(cvrp.get_demands()[customer]);
+
+                if route_demands[to] + demand > u64::from(cvrp.get_capacity()) {
+                    continue;
+                }
+
+                for j in 0..=to_route.len() {
+                    let (before, after) = (at(cvrp, to_route, j), at(cvrp, to_route, j + 1));
+
+                    let removed = remove_removed + edge(cvrp, before, after);
+                    let added =
+                        remove_added + edge(cvrp, before, customer) + edge(cvrp, customer, after);
+
+                    keep_better(&mut best, removed, added, Move::Relocate { from, i, to, j });
+                }
+            }
+        }
+    }
+
+    best
+}
+
+fn best_two_opt_star<W: Weight>(
+    cvrp: &Cvrp<W>,
+    routes: &[Vec<usize>],
+) -> Option<(W::Length, Move)> {
+    let capacity = u64::from(cvrp.get_capacity());
+
+    // demands[r][i] is the demand of the first i customers of route r
+    let prefix_demands = routes
+        .iter()
+        .map(|route| {
+            let mut demands = vec![0];
+
+            for &customer in route {
+                demands.push(demands[demands.len() - 1] + u64::from(cvrp.get_demands()[customer]));
+            }
+
+            demands
+        })
+        .collect::<Vec<_>>();
+
+    let mut best = None;
+
+    for (first, first_route) in routes.iter().enumerate() {
+        for (second, second_route) in routes.iter().enumerate().skip(first + 1) {
+            let first_demands = &prefix_demands[first];
+            let second_demands = &prefix_demands[second];
+
+            for i in 0..=first_route.len() {
+                for j in 0..=second_route.len() {
+                    let whole_routes =
+                        (i == 0 && j == 0) || (i == first_route.len() && j == second_route.len());
+
+                    let first_demand =
+                        first_demands[i] + second_demands[second_route.len()] - second_demands[j];
+                    let second_demand =
+                        second_demands[j] + first_demands[first_route.len()] - first_demands[i];
+
+                    if whole_routes || first_demand > capacity || second_demand > capacity {
+                        continue;
+                    }
+
+                    let (first_end, first_next) =
+                        (at(cvrp, first_route, i), at(cvrp, first_route, i + 1));
+                    let (second_end, second_next) =
+                        (at(cvrp, second_route, j), at(cvrp, second_route, j + 1));
+
+                    let removed =
+                        edge(cvrp, first_end, first_next) + edge(cvrp, second_end, second_next);
+                    let added =
+                        edge(cvrp, first_end, second_next) + edge(cvrp, second_end, first_next);
+
+                    keep_better(
+                        &mut best,
+                        removed,
+                        added,
+                        Move::TwoOptStar {
+                            first,
+                            i,
+                            second,
+                            j,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClarkeWright, TspParser};
+
+    #[test]
+    fn local_search_finds_optimum() {
+        let cvrp = Cvrp::from_file("cvrp").expect("Couldn't parse file");
+
+        let initial = ClarkeWright::new().get_solution(&cvrp);
+        let improved = CvrpLocalSearch::new(ClarkeWright::new()).get_solution(&cvrp);
+
+        let initial_len = cvrp.get_solution_len(&initial).unwrap();
+        let improved_len = cvrp
+            .get_solution_len(&improved)
+            .expect("Has to be valid solution");
+
+        assert!(improved_len <= initial_len);
+        assert_eq!(435, improved_len);
+    }
+
+    struct SingleRoutes;
+
+    impl CvrpHeuristic for SingleRoutes {
+        fn get_solution(&self, cvrp: &Cvrp) -> CvrpSolution {
+            CvrpSolution::new(cvrp.customers().map(|customer| vec![customer]).collect())
+        }
+    }
+
+    #[test]
+    fn local_search_merges_routes() {
+        let cvrp = Cvrp::from_file("cvrp").expect("Couldn't parse file");
+
+        let solution = CvrpLocalSearch::new(SingleRoutes).get_solution(&cvrp);
+        let solution_len = cvrp
+            .get_solution_len(&solution)
+            .expect("Has to be valid solution");
+
+        assert!(solution.get_routes().len() < 8);
+        assert!(solution_len < 654);
+    }
+
+    fn check_move_improvements(cvrp: &Cvrp, routes: &[Vec<usize>]) {
+        let routes_len = |routes: &[Vec<usize>]| -> u64 {
+            routes.iter().map(|route| cvrp.get_route_len(route)).sum()
+        };
+
+        for (improvement, best_move) in [
+            best_two_opt(cvrp, routes),
+            best_relocate(cvrp, routes),
+            best_two_opt_star(cvrp, routes),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            let mut moved = routes.to_vec();
+            best_move.apply(&mut moved);
+
+            assert_eq!(
+                routes_len(routes),
+                routes_len(&moved) + improvement,
+                "{:?}",
+                best_move
+            );
+        }
+    }
+
+    #[test]
+    fn moves_match_route_lens() {
+        let cvrp = Cvrp::from_file("cvrp").expect("Couldn't parse file");
+
+        check_move_improvements(&cvrp, &[vec![8, 3, 1], vec![2, 7, 4], vec![6, 5]]);
+
+        let tsp = TspParser::from_file("test_files/br17.atsp").expect("Couldn't parse file");
+        let cvrp = Cvrp::new(tsp, 6, vec![1; 17], 0);
+
+        check_move_improvements(
+            &cvrp,
+            &[
+                vec![5, 3, 1, 4, 2],
+                vec![6, 11, 7, 8, 10, 9],
+                vec![16, 12, 14, 13, 15],
+            ],
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use crate::matrix::{DenseMatrix, PackedMatrix};
+use crate::weight::Weight;
+
+// TSPLIB truncates PI, using the exact constant changes some GEO distances
+#[allow(clippy::approx_constant)]
+const GEO_PI: f64 = 3.141592;
+const GEO_EARTH_RADIUS: f64 = 6378.388;
+
+/// TSPLIB distance functions for two dimensional node coordinates.
+///
+/// Every variant follows the rounding rules from the TSPLIB documentation,
+/// so route lengths match the published optimal tour lengths.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Metric {
+    Euc2d,
+    Ceil2d,
+    Man2d,
+    Max2d,
+    Geo,
+    Att,
+}
+
+impl Metric {
+    pub fn distance(&self, (x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> u32 {
+        let xd = x1 - x2;
+        let yd = y1 - y2;
+
+        match self {
+            Metric::Euc2d => nint((xd * xd + yd * yd).sqrt()),
+            Metric::Ceil2d => (xd * xd + yd * yd).sqrt().ceil() as u32,
+            Metric::Man2d => nint(xd.abs() + yd.abs()),
+            Metric::Max2d => nint(xd.abs()).max(nint(yd.abs())),
+            Metric::Geo => Metric::geo_distance((x1, y1), (x2, y2)),
+            Metric::Att => {
+                let rij = ((xd * xd + yd * yd) / 10.0).sqrt();
+                let tij = nint(rij);
+
+                if (tij as f64) < rij {
+                    tij + 1
+                } else {
+                    tij
+                }
+            }
+        }
+    }
+
+    fn geo_distance((x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> u32 {
+        let (latitude1, longitude1) = (geo_radians(x1), geo_radians(y1));
+        let (latitude2, longitude2) = (geo_radians(x2), geo_radians(y2));
+
+        let q1 = (longitude1 - longitude2).cos();
+        let q2 = (latitude1 - latitude2).cos();
+        let q3 = (latitude1 + latitude2).cos();
+
+        (GEO_EARTH_RADIUS * (0.5 * ((1.0 + q1) * q2 - (1.0 - q1) * q3)).acos() + 1.0) as u32
+    }
+}
+
+pub fn euc_3d_distance((x1, y1, z1): (f64, f64, f64), (x2, y2, z2): (f64, f64, f64)) -> u32 {
+    let xd = x1 - x2;
+    let yd = y1 - y2;
+    let zd = z1 - z2;
+
+    nint((xd * xd + yd * yd + zd * zd).sqrt())
+}
+
+fn nint(x: f64) -> u32 {
+    (x + 0.5) as u32
+}
+
+// coordinates are given as DDD.MM where MM are minutes, not decimal degrees
+fn geo_radians(x: f64) -> f64 {
+    let degrees = x.trunc();
+    let minutes = x - degrees;
+
+    GEO_PI * (degrees + 5.0 * minutes / 3.0) / 180.0
+}
+
+/// Source of edge weights for a `Tsp`.
+///
+/// Explicit instances keep the whole matrix in memory, symmetric ones given
+/// as a triangle only keep that triangle. Coordinate instances
+/// only keep the points and compute every distance when asked, so even the
+/// biggest EUC_2D files need memory linear in dimension.
+///
+/// Weights computed from coordinates are rounded by the metric and only then
+/// converted into `W`.
+///
+/// `Graph` keeps sorted adjacency lists of a sparse graph, its edges weigh 0
+/// and every missing edge weighs 1.
+#[derive(Debug, Clone)]
+pub enum Distances<W = u32> {
+    Matrix(DenseMatrix<W>),
+    Packed(PackedMatrix<W>),
+    Coordinates(Vec<(f64, f64)>, Metric),
+    Coordinates3d(Vec<(f64, f64, f64)>),
+    Graph(Vec<Vec<usize>>),
+}
+
+impl<W: Weight> Distances<W> {
+    #[inline]
+    pub fn get(&self, from: usize, to: usize) -> W {
+        match self {
+            Distances::Matrix(matrix) => matrix.get(from, to),
+            Distances::Packed(matrix) => matrix.get(from, to),
+            Distances::Coordinates(_, _) | Distances::Coordinates3d(_) | Distances::Graph(_)
+                if from == to =>
+            {
+                W::ZERO
+            }
+            Distances::Coordinates(coords, metric) => {
+                W::from_u32(metric.distance(coords[from], coords[to]))
+            }
+            Distances::Coordinates3d(coords) => {
+                W::from_u32(euc_3d_distance(coords[from], coords[to]))
+            }
+            Distances::Graph(adjacency) => match adjacency[from].binary_search(&to) {
+                Ok(_) => W::ZERO,
+                Err(_) => W::ONE,
+            },
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Distances::Matrix(matrix) => matrix.get_dimension(),
+            Distances::Packed(matrix) => matrix.get_dimension(),
+            Distances::Coordinates(coords, _) => coords.len(),
+            Distances::Coordinates3d(coords) => coords.len(),
+            Distances::Graph(adjacency) => adjacency.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn to_matrix(&self) -> Vec<Vec<W>> {
+        (0..self.len())
+            .map(|from| (0..self.len()).map(|to| self.get(from, to)).collect())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coordinates_match_matrix() {
+        let coords = vec![(0.0, 10.0), (0.0, 0.0), (5.0, 5.0)];
+        let distances: Distances = Distances::Coordinates(coords, Metric::Euc2d);
+
+        assert_eq!(
+            vec![vec![0, 10, 7], vec![10, 0, 7], vec![7, 7, 0]],
+            distances.to_matrix()
+        );
+    }
+
+    #[test]
+    fn rounding_rules_work() {
+        let (p1, p2) = ((0.0, 0.0), (2.4, 1.3));
+
+        assert_eq!(3, Metric::Euc2d.distance(p1, p2));
+        assert_eq!(3, Metric::Ceil2d.distance(p1, p2));
+        assert_eq!(4, Metric::Man2d.distance(p1, p2));
+        assert_eq!(2, Metric::Max2d.distance(p1, p2));
+        assert_eq!(1, Metric::Att.distance(p1, p2));
+        assert_eq!(4, euc_3d_distance((0.0, 0.0, 0.0), (1.0, 2.0, 3.0)));
+    }
+
+    #[test]
+    fn geo_diagonal_is_zero() {
+        let distances: Distances =
+            Distances::Coordinates(vec![(38.24, 20.42), (39.57, 26.15)], Metric::Geo);
+
+        assert_eq!(0, distances.get(1, 1));
+        assert_eq!(509, distances.get(0, 1));
+    }
+
+    #[test]
+    fn matrix_get_works() {
+        let distances: Distances =
+            Distances::Matrix(DenseMatrix::from_rows(vec![vec![0, 1], vec![2, 0]]));
+
+        assert_eq!(1, distances.get(0, 1));
+        assert_eq!(2, distances.get(1, 0));
+    }
+
+    #[test]
+    fn packed_matches_matrix() {
+        let rows: Vec<Vec<u32>> = vec![vec![0, 1, 2], vec![1, 0, 3], vec![2, 3, 0]];
+        let packed = PackedMatrix::from_fn(3, |from, to| rows[from][to]);
+
+        assert_eq!(rows, Distances::Packed(packed).to_matrix());
+    }
+
+    #[test]
+    fn graph_get_works() {
+        let distances: Distances = Distances::Graph(vec![vec![1, 2], vec![0], vec![0]]);
+
+        assert_eq!(
+            vec![vec![0, 0, 0], vec![0, 0, 1], vec![0, 1, 0]],
+            distances.to_matrix()
+        );
+    }
+}
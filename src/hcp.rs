@@ -0,0 +1,224 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::str::FromStr;
+
+use crate::distances::Distances;
+use crate::tsp::{check_permutation, Tsp, TspRouteError, TspType};
+use crate::tsp_parser::edge_data::{parse_adj_list, parse_edge_list};
+use crate::tsp_parser::tokens::Tokens;
+use crate::tsp_parser::{TokenContext, TspHeader, TspParsingError};
+
+/// Hamiltonian cycle problem on an undirected graph, kept as sorted
+/// adjacency lists so big sparse instances stay small.
+#[derive(Debug, Clone)]
+pub struct Hcp {
+    header: TspHeader,
+    adjacency: Vec<Vec<usize>>,
+}
+
+impl Hcp {
+    pub fn new(dimension: usize, edges: &[(usize, usize)]) -> Hcp {
+        let mut adjacency = vec![Vec::new(); dimension];
+
+        for &(from, to) in edges.iter().filter(|(from, to)| from != to) {
+            adjacency[from].push(to);
+            adjacency[to].push(from);
+        }
+
+        for neighbours in adjacency.iter_mut() {
+            neighbours.sort_unstable();
+            neighbours.dedup();
+        }
+
+        Hcp {
+            header: TspHeader {
+                dimension,
+                ..TspHeader::default()
+            },
+            adjacency,
+        }
+    }
+
+    pub fn with_header(mut self, header: TspHeader) -> Hcp {
+        self.header = header;
+        self
+    }
+
+    pub fn from_file(filename: &str) -> Result<Hcp, TspParsingError> {
+        let file = File::open(filename).map_err(TspParsingError::FileDoesntExist)?;
+
+        Hcp::from_reader(BufReader::new(file))
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Hcp, TspParsingError> {
+        let mut tokens = Tokens::new(reader);
+
+        let (header, section) = TspHeader::parse(&mut tokens)?;
+        let dimension = header.dimension;
+
+        let mut section = Some(section);
+        let mut edges = None;
+
+        while let Some(current) = section {
+            tokens.set_section(&current);
+
+            match current.as_str() {
+                "EOF" => break,
+                "EDGE_DATA_SECTION" => {
+                    edges = Some(match header.edge_data_format.as_deref() {
+                        Some("EDGE_LIST") => parse_edge_list(&mut tokens, dimension)?,
+                        Some("ADJ_LIST") => parse_adj_list(&mut tokens, dimension)?,
+                        Some(format) => {
+                            return Err(TspParsingError::UnknownEdgeDataFormat(TokenContext {
+                                line: tokens.line_number(),
+                                column: 1,
+                                token: format.to_string(),
+                                section: current,
+                            }))
+                        }
+                        None => return Err(TspParsingError::NoEdgeDataFormat),
+                    })
+                }
+                _ => {}
+            }
+
+            section = tokens.next_section()?;
+        }
+
+        let edges = edges.ok_or_else(|| TspParsingError::NotEnoughData {
+            section: String::from("EDGE_DATA_SECTION"),
+            line: tokens.line_number(),
+        })?;
+
+        Ok(Hcp::new(dimension, &edges).with_header(header))
+    }
+
+    /// Symmetric `Tsp` where edges of the graph weigh 0 and all others 1,
+    /// so a route of length 0 is a Hamiltonian cycle.
+    pub fn to_tsp(&self) -> Tsp {
+        Tsp::new(
+            Distances::Graph(self.adjacency.clone()),
+            self.get_dimension(),
+            TspType::Symmetric,
+        )
+        .with_header(self.header.clone())
+    }
+
+    pub fn check_hamiltonian_cycle(&self, route: &[usize]) -> Result<(), TspRouteError> {
+        check_permutation(route, self.get_dimension())?;
+
+        for i in 0..route.len() {
+            let (from, to) = (route[i], route[(i + 1) % route.len()]);
+
+            if !self.has_edge(from, to) {
+                return Err(TspRouteError::MissingEdge(from, to));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn has_edge(&self, from: usize, to: usize) -> bool {
+        self.adjacency[from].binary_search(&to).is_ok()
+    }
+
+    pub fn get_neighbours(&self, vertex: usize) -> &[usize] {
+        &self.adjacency[vertex]
+    }
+
+    pub fn get_dimension(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    pub fn get_header(&self) -> &TspHeader {
+        &self.header
+    }
+}
+
+impl FromStr for Hcp {
+    type Err = TspParsingError;
+
+    fn from_str(content: &str) -> Result<Hcp, TspParsingError> {
+        Hcp::from_reader(content.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NearestNeighbourOptimized, TspHeuristic, TwoOpt};
+
+    #[test]
+    fn edge_list_matches_adj_list() {
+        let edge_list = Hcp::from_file("hcp_edge_list").expect("Couldn't parse file");
+        let adj_list = Hcp::from_file("hcp_adj_list").expect("Couldn't parse file");
+
+        assert_eq!(8, edge_list.get_dimension());
+        assert_eq!(&[1, 2, 4], edge_list.get_neighbours(0));
+
+        for vertex in 0..8 {
+            assert_eq!(
+                edge_list.get_neighbours(vertex),
+                adj_list.get_neighbours(vertex)
+            );
+        }
+    }
+
+    #[test]
+    fn hamiltonian_cycle_check() {
+        let hcp = Hcp::from_file("hcp_edge_list").expect("Couldn't parse file");
+
+        assert!(hcp
+            .check_hamiltonian_cycle(&[0, 1, 3, 2, 6, 7, 5, 4])
+            .is_ok());
+        assert!(matches!(
+            hcp.check_hamiltonian_cycle(&[0, 1, 2, 3, 4, 5, 6, 7]),
+            Err(TspRouteError::MissingEdge(1, 2))
+        ));
+        assert!(matches!(
+            hcp.check_hamiltonian_cycle(&[0, 1, 3, 2, 6, 7, 5]),
+            Err(TspRouteError::TooShort)
+        ));
+    }
+
+    #[test]
+    fn heuristics_work_on_tsp() {
+        let hcp = Hcp::from_file("hcp_edge_list").expect("Couldn't parse file");
+        let tsp = hcp.to_tsp();
+
+        assert_eq!(0, tsp.get_route_len(&[0, 1, 3, 2, 6, 7, 5, 4]).unwrap());
+        assert_eq!(4, tsp.get_route_len(&[0, 1, 2, 3, 4, 5, 6, 7]).unwrap());
+
+        let route = TwoOpt::new(NearestNeighbourOptimized::new()).get_route(&tsp);
+        let route_len = tsp.get_route_len(&route).expect("Has to be valid route");
+
+        assert_eq!(route_len == 0, hcp.check_hamiltonian_cycle(&route).is_ok());
+    }
+
+    #[test]
+    fn missing_edge_data_format() {
+        let content = std::fs::read_to_string("hcp_edge_list").expect("file doesn't exist");
+        let without_format = content.replace("EDGE_DATA_FORMAT: EDGE_LIST\n", "");
+
+        assert!(matches!(
+            without_format.parse::<Hcp>(),
+            Err(TspParsingError::NoEdgeDataFormat)
+        ));
+    }
+
+    #[test]
+    fn unknown_edge_data_format() {
+        let content = std::fs::read_to_string("hcp_edge_list").expect("file doesn't exist");
+        let adj_matrix = content.replace(
+            "EDGE_DATA_FORMAT: EDGE_LIST",
+            "EDGE_DATA_FORMAT: ADJ_MATRIX",
+        );
+
+        match adj_matrix.parse::<Hcp>() {
+            Err(TspParsingError::UnknownEdgeDataFormat(context)) => {
+                assert_eq!("ADJ_MATRIX", context.token)
+            }
+            _ => panic!("expected UnknownEdgeDataFormat"),
+        }
+    }
+}
@@ -0,0 +1,363 @@
+use crate::distances::{Distances, Metric};
+use crate::tsp::{Tsp, TspType};
+use crate::weight::Weight;
+
+/// Most points kept in a leaf.
+const BUCKET_SIZE: usize = 8;
+
+/// 2D tree of node positions, nearest points and points within a radius
+/// are found in logarithmic time on average. Points can be deleted, so
+/// construction heuristics can keep only the unvisited ones.
+#[derive(Debug, Clone)]
+pub struct KdTree {
+    points: Vec<(f64, f64)>,
+    nodes: Vec<Node>,
+    leaf_of: Vec<usize>,
+    len: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    parent: Option<usize>,
+    // points left in the subtree
+    len: usize,
+    kind: NodeKind,
+}
+
+#[derive(Debug, Clone)]
+enum NodeKind {
+    // points at most `value` on the axis are on the left, at least on the right
+    Split {
+        is_x: bool,
+        value: f64,
+        children: [usize; 2],
+    },
+    Leaf(Vec<usize>),
+}
+
+impl KdTree {
+    /// Tree of all `points`, indexed by their positions in it.
+    pub fn new(points: Vec<(f64, f64)>) -> KdTree {
+        let mut tree = KdTree {
+            leaf_of: vec![0; points.len()],
+            len: points.len(),
+            nodes: Vec::new(),
+            points,
+        };
+
+        let mut indices: Vec<_> = (0..tree.points.len()).collect();
+        tree.build(&mut indices, None);
+
+        tree
+    }
+
+    /// Tree of node coordinates of a symmetric instance whose weights grow
+    /// with the Euclidean distance, so the nearest point is joined to the
+    /// lightest edge.
+    pub fn from_tsp<W: Weight>(tsp: &Tsp<W>) -> Option<KdTree> {
+        match (tsp.get_distances(), tsp.get_tsp_type()) {
+            (
+                Distances::Coordinates(coords, Metric::Euc2d | Metric::Ceil2d | Metric::Att),
+                TspType::Symmetric,
+            ) => Some(KdTree::new(coords.clone())),
+            _ => None,
+        }
+    }
+
+    fn build(&mut self, indices: &mut [usize], parent: Option<usize>) -> usize {
+        let node = self.nodes.len();
+
+        self.nodes.push(Node {
+            parent,
+            len: indices.len(),
+            kind: NodeKind::Leaf(Vec::new()),
+        });
+
+        if indices.len() <= BUCKET_SIZE {
+            for &index in indices.iter() {
+                self.leaf_of[index] = node;
+            }
+
+            self.nodes[node].kind = NodeKind::Leaf(indices.to_vec());
+
+            return node;
+        }
+
+        let spread = |coordinate: fn((f64, f64)) -> f64| {
+            let values = indices.iter().map(|&index| coordinate(self.points[index]));
+
+            values.clone().fold(f64::MIN, f64::max) - values.fold(f64::MAX, f64::min)
+        };
+
+        let is_x = spread(|(x, _)| x) >= spread(|(_, y)| y);
+        let coordinate = |index: usize| {
+            let (x, y) = self.points[index];
+
+            if is_x {
+                x
+            } else {
+                y
+            }
+        };
+
+        let middle = indices.len() / 2;
+
+        indices.select_nth_unstable_by(middle, |&first, &second| {
+            coordinate(first).total_cmp(&coordinate(second))
+        });
+
+        let value = coordinate(indices[middle]);
+        let (left, right) = indices.split_at_mut(middle);
+
+        let children = [self.build(left, Some(node)), self.build(right, Some(node))];
+
+        self.nodes[node].kind = NodeKind::Split {
+            is_x,
+            value,
+            children,
+        };
+
+        node
+    }
+
+    pub fn get_point(&self, index: usize) -> (f64, f64) {
+        self.points[index]
+    }
+
+    /// Number of points not deleted.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        match &self.nodes[self.leaf_of[index]].kind {
+            NodeKind::Leaf(indices) => indices.contains(&index),
+            NodeKind::Split { .. } => false,
+        }
+    }
+
+    /// Leaves the point out of all later queries.
+    pub fn delete(&mut self, index: usize) {
+        let leaf = self.leaf_of[index];
+
+        let NodeKind::Leaf(indices) = &mut self.nodes[leaf].kind else {
+            return;
+        };
+
+        let Some(position) = indices.iter().position(|&other| other == index) else {
+            return;
+        };
+
+        indices.swap_remove(position);
+        self.len -= 1;
+
+        let mut node = Some(leaf);
+
+        while let Some(curr_node) = node {
+            self.nodes[curr_node].len -= 1;
+            node = self.nodes[curr_node].parent;
+        }
+    }
+
+    /// Nearest point to `point` accepted by `filter`.
+    pub fn nearest(&self, point: (f64, f64), filter: impl Fn(usize) -> bool) -> Option<usize> {
+        self.nearest_k(point, 1, filter).first().copied()
+    }
+
+    /// Up to `count` nearest points to `point` accepted by `filter`, nearest
+    /// first.
+    pub fn nearest_k(
+        &self,
+        point: (f64, f64),
+        count: usize,
+        filter: impl Fn(usize) -> bool,
+    ) -> Vec<usize> {
+        let mut nearest = Vec::with_capacity(count + 1);
+
+        if count > 0 && !self.is_empty() {
+            self.search_nearest(0, point, count, &filter, &mut nearest);
+        }
+
+        nearest.into_iter().map(|(_, index)| index).collect()
+    }
+
+    // `nearest` holds squared distances and points, sorted
+    fn search_nearest(
+        &self,
+        node: usize,
+        point: (f64, f64),
+        count: usize,
+        filter: &impl Fn(usize) -> bool,
+        nearest: &mut Vec<(f64, usize)>,
+    ) {
+        match &self.nodes[node].kind {
+            NodeKind::Leaf(indices) => {
+                for &index in indices.iter().filter(|&&index| filter(index)) {
+                    let distance = squared_distance(point, self.points[index]);
+
+                    if nearest.len() == count && distance >= nearest[count - 1].0 {
+                        continue;
+                    }
+
+                    let position = nearest.partition_point(|&(other, _)| other <= distance);
+
+                    nearest.insert(position, (distance, index));
+                    nearest.truncate(count);
+                }
+            }
+            &NodeKind::Split {
+                is_x,
+                value,
+                children,
+            } => {
+                let difference = if is_x { point.0 } else { point.1 } - value;
+                let [near, far] = if difference < 0.0 {
+                    children
+                } else {
+                    [children[1], children[0]]
+                };
+
+                if self.nodes[near].len > 0 {
+                    self.search_nearest(near, point, count, filter, nearest);
+                }
+
+                let is_far_closer =
+                    nearest.len() < count || difference * difference < nearest[count - 1].0;
+
+                if self.nodes[far].len > 0 && is_far_closer {
+                    self.search_nearest(far, point, count, filter, nearest);
+                }
+            }
+        }
+    }
+
+    /// Points at most `radius` away from `point`, in no particular order.
+    pub fn within_radius(&self, point: (f64, f64), radius: f64) -> Vec<usize> {
+        let mut found = Vec::new();
+        let mut stack = vec![0];
+
+        while let Some(node) = stack.pop() {
+            if self.nodes[node].len == 0 {
+                continue;
+            }
+
+            match &self.nodes[node].kind {
+                NodeKind::Leaf(indices) => found.extend(indices.iter().filter(|&&index| {
+                    squared_distance(point, self.points[index]) <= radius * radius
+                })),
+                &NodeKind::Split {
+                    is_x,
+                    value,
+                    children,
+                } => {
+                    let coordinate = if is_x { point.0 } else { point.1 };
+
+                    if coordinate - radius <= value {
+                        stack.push(children[0]);
+                    }
+
+                    if coordinate + radius >= value {
+                        stack.push(children[1]);
+                    }
+                }
+            }
+        }
+
+        found
+    }
+}
+
+fn squared_distance((x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> f64 {
+    (x1 - x2) * (x1 - x2) + (y1 - y2) * (y1 - y2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TspParser;
+
+    fn brute_force_nearest(points: &[(f64, f64)], point: (f64, f64), count: usize) -> Vec<f64> {
+        let mut distances: Vec<_> = points
+            .iter()
+            .map(|&other| squared_distance(point, other))
+            .collect();
+
+        distances.sort_by(f64::total_cmp);
+        distances.truncate(count);
+        distances
+    }
+
+    #[test]
+    fn nearest_points_match_brute_force() {
+        let tsp = TspParser::from_file("test_files/pr1002.tsp").expect("Couldn't parse file");
+        let tree = KdTree::from_tsp(&tsp).expect("has euclidean coordinates");
+        let points = tsp.coordinates().unwrap();
+
+        for &point in points.iter().step_by(7).chain([&(0.0, 0.0), &(1e6, -3.5)]) {
+            let nearest: Vec<_> = tree
+                .nearest_k(point, 10, |_| true)
+                .into_iter()
+                .map(|index| squared_distance(point, points[index]))
+                .collect();
+
+            assert_eq!(brute_force_nearest(points, point, 10), nearest);
+        }
+    }
+
+    #[test]
+    fn deleted_points_are_skipped() {
+        let points = vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (10.0, 10.0)];
+        let mut tree = KdTree::new(points);
+
+        assert_eq!(Some(1), tree.nearest((1.2, 0.0), |_| true));
+
+        tree.delete(1);
+        tree.delete(1);
+
+        assert_eq!(3, tree.len());
+        assert!(!tree.contains(1));
+        assert_eq!(Some(2), tree.nearest((1.2, 0.0), |_| true));
+        assert_eq!(Some(0), tree.nearest((1.2, 0.0), |index| index != 2));
+
+        for index in [0, 2, 3] {
+            tree.delete(index);
+        }
+
+        assert!(tree.is_empty());
+        assert_eq!(None, tree.nearest((1.2, 0.0), |_| true));
+    }
+
+    #[test]
+    fn radius_search_finds_all_points() {
+        let tsp = TspParser::from_file("test_files/pr1002.tsp").expect("Couldn't parse file");
+        let mut tree = KdTree::from_tsp(&tsp).expect("has euclidean coordinates");
+        let points = tsp.coordinates().unwrap();
+
+        tree.delete(5);
+
+        let center = points[10];
+        let radius = 1000.0;
+
+        let mut found = tree.within_radius(center, radius);
+        found.sort_unstable();
+
+        let expected: Vec<_> = (0..points.len())
+            .filter(|&index| index != 5)
+            .filter(|&index| squared_distance(center, points[index]) <= radius * radius)
+            .collect();
+
+        assert!(expected.len() > 1);
+        assert_eq!(expected, found);
+    }
+
+    #[test]
+    fn only_euclidean_instances_get_tree() {
+        let tsp = TspParser::from_file("test_files/br17.atsp").expect("Couldn't parse file");
+
+        assert!(KdTree::from_tsp(&tsp).is_none());
+    }
+}
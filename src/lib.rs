@@ -1,16 +1,44 @@
 pub mod neighbourhood;
+mod atsp_transform;
+mod candidates;
+mod cvrp;
+mod distances;
+mod hcp;
+mod kd_tree;
+mod matrix;
+mod sop;
+mod tour;
 mod tsp;
+mod tsp_analysis;
+mod tsp_cache;
 mod tsp_heuristic;
 mod tsp_parser;
+mod tsp_writer;
+mod weight;
 mod tsp_tests;
 
+pub use crate::atsp_transform::SymmetricTransform;
+pub use crate::candidates::{CandidateLists, CandidateSource, DEFAULT_CANDIDATES};
+pub use crate::cvrp::*;
+pub use crate::distances::*;
+pub use crate::hcp::Hcp;
+pub use crate::kd_tree::KdTree;
+pub use crate::matrix::{DenseMatrix, PackedMatrix};
+pub use crate::sop::*;
+pub use crate::tour::Tour;
 pub use crate::tsp::*;
+pub use crate::tsp_analysis::TspAnalysis;
+pub use crate::tsp_cache::TspCache;
 pub use crate::tsp_heuristic::*;
-pub use crate::tsp_parser::{TspParser, TspFileType};
+pub use crate::tsp_parser::{
+    Compression, ProblemType, TokenContext, TspFileType, TspHeader, TspParser, TspParsingError,
+};
+pub use crate::tsp_writer::TspWriter;
+pub use crate::weight::{RouteLength, Weight};
 
-pub struct HeuristicBench {
+pub struct HeuristicBench<W: Weight = u32> {
     pub route: Vec<usize>,
-    pub route_len: u32,
+    pub route_len: W::Length,
     pub duration: std::time::Duration,
 }
 
@@ -27,13 +55,14 @@ pub fn get_problem_with_bench(file: &str) -> Tsp {
     tsp
 }
 
-pub fn run_heuristic_with_bench<H>(tsp: &Tsp, heuristic: H) -> HeuristicBench
+pub fn run_heuristic_with_bench<W, H>(tsp: &Tsp<W>, heuristic: H) -> HeuristicBench<W>
 where
-    H: TspHeuristic,
+    W: Weight,
+    H: TspHeuristic<W>,
 {
     let start = std::time::Instant::now();
 
-    let route = heuristic.get_route(&tsp);
+    let route = heuristic.get_route(tsp);
     let route_len = tsp.get_route_len(&route).expect("Has to be valid route");
 
     HeuristicBench {
@@ -1,7 +1,7 @@
 use tsp_parser::*;
 
 pub const K: usize = 1000;
-pub const FILE: &'static str = "test_files/d1655.tsp";
+pub const FILE: &str = "test_files/d1655.tsp";
 
 fn main() {
     println!("File: {}", FILE);
@@ -35,6 +35,15 @@ fn main() {
     println!("Nearest neighbour optimized route len: {}", route_len);
     println!("Time to calculate nearest neighbour opt: {:?}\n", duration);
 
+    let HeuristicBench {
+        route: _,
+        route_len,
+        duration,
+    } = run_heuristic_with_bench(&tsp, Greedy::new());
+
+    println!("Greedy route len: {}", route_len);
+    println!("Time to calculate greedy: {:?}\n", duration);
+
     // at this moment only invert neighbourhood is turned on
     // works properly only for symetric tsp
     let HeuristicBench {
@@ -45,4 +54,22 @@ fn main() {
 
     println!("Two opt route len: {}", route_len);
     println!("Time to calculate two opt: {:?}\n", duration);
+
+    let HeuristicBench {
+        route: _,
+        route_len,
+        duration,
+    } = run_heuristic_with_bench(&tsp, ThreeOpt::new(NearestNeighbourOptimized::new()));
+
+    println!("Three opt route len: {}", route_len);
+    println!("Time to calculate three opt: {:?}\n", duration);
+
+    let HeuristicBench {
+        route: _,
+        route_len,
+        duration,
+    } = run_heuristic_with_bench(&tsp, LinKernighan::new(NearestNeighbourOptimized::new()));
+
+    println!("Lin-Kernighan route len: {}", route_len);
+    println!("Time to calculate Lin-Kernighan: {:?}\n", duration);
 }
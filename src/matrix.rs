@@ -0,0 +1,187 @@
+use crate::weight::Weight;
+
+/// Square matrix of edge weights kept row by row in a single allocation.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DenseMatrix<W = u32> {
+    dimension: usize,
+    weights: Vec<W>,
+}
+
+impl<W: Weight> DenseMatrix<W> {
+    pub fn new(dimension: usize) -> DenseMatrix<W> {
+        DenseMatrix {
+            dimension,
+            weights: vec![W::ZERO; dimension * dimension],
+        }
+    }
+
+    pub(crate) fn from_weights(dimension: usize, weights: Vec<W>) -> DenseMatrix<W> {
+        assert_eq!(dimension * dimension, weights.len(), "wrong number of weights");
+
+        DenseMatrix { dimension, weights }
+    }
+
+    pub fn from_fn<F>(dimension: usize, mut weight: F) -> DenseMatrix<W>
+    where
+        F: FnMut(usize, usize) -> W,
+    {
+        let weights = (0..dimension)
+            .flat_map(|from| (0..dimension).map(move |to| (from, to)))
+            .map(|(from, to)| weight(from, to))
+            .collect();
+
+        DenseMatrix { dimension, weights }
+    }
+
+    /// Panics when the rows don't form a square matrix.
+    pub fn from_rows(rows: Vec<Vec<W>>) -> DenseMatrix<W> {
+        let dimension = rows.len();
+
+        assert!(
+            rows.iter().all(|row| row.len() == dimension),
+            "matrix has to be square"
+        );
+
+        DenseMatrix {
+            dimension,
+            weights: rows.into_iter().flatten().collect(),
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, from: usize, to: usize) -> W {
+        self.weights[from * self.dimension + to]
+    }
+
+    #[inline]
+    pub fn set(&mut self, from: usize, to: usize, weight: W) {
+        self.weights[from * self.dimension + to] = weight;
+    }
+
+    pub fn row(&self, from: usize) -> &[W] {
+        &self.weights[from * self.dimension..(from + 1) * self.dimension]
+    }
+
+    /// Weights in the order they are stored in.
+    pub fn weights(&self) -> &[W] {
+        &self.weights
+    }
+
+    pub fn get_dimension(&self) -> usize {
+        self.dimension
+    }
+}
+
+/// Symmetric matrix which keeps only the upper triangle with the diagonal,
+/// packed row by row, so it needs about half the memory of `DenseMatrix`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PackedMatrix<W = u32> {
+    dimension: usize,
+    weights: Vec<W>,
+}
+
+impl<W: Weight> PackedMatrix<W> {
+    pub fn new(dimension: usize) -> PackedMatrix<W> {
+        PackedMatrix {
+            dimension,
+            weights: vec![W::ZERO; dimension * (dimension + 1) / 2],
+        }
+    }
+
+    pub(crate) fn from_weights(dimension: usize, weights: Vec<W>) -> PackedMatrix<W> {
+        assert_eq!(dimension * (dimension + 1) / 2, weights.len(), "wrong number of weights");
+
+        PackedMatrix { dimension, weights }
+    }
+
+    /// Only asks for weights with `from <= to`.
+    pub fn from_fn<F>(dimension: usize, mut weight: F) -> PackedMatrix<W>
+    where
+        F: FnMut(usize, usize) -> W,
+    {
+        let weights = (0..dimension)
+            .flat_map(|from| (from..dimension).map(move |to| (from, to)))
+            .map(|(from, to)| weight(from, to))
+            .collect();
+
+        PackedMatrix { dimension, weights }
+    }
+
+    #[inline]
+    pub fn get(&self, from: usize, to: usize) -> W {
+        self.weights[self.index(from, to)]
+    }
+
+    /// Sets the weight in both directions.
+    #[inline]
+    pub fn set(&mut self, from: usize, to: usize, weight: W) {
+        let index = self.index(from, to);
+
+        self.weights[index] = weight;
+    }
+
+    /// Weights in the order they are stored in.
+    pub fn weights(&self) -> &[W] {
+        &self.weights
+    }
+
+    pub fn get_dimension(&self) -> usize {
+        self.dimension
+    }
+
+    // rows before `row` have lengths dimension, dimension - 1, ... and
+    // every row starts at its diagonal
+    #[inline]
+    fn index(&self, from: usize, to: usize) -> usize {
+        let (row, column) = if from <= to { (from, to) } else { (to, from) };
+
+        row * self.dimension - row * (row + 1) / 2 + column
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dense_matrix_works() {
+        let mut matrix: DenseMatrix =
+            DenseMatrix::from_rows(vec![vec![0, 1, 2], vec![3, 0, 4], vec![5, 6, 0]]);
+
+        assert_eq!(3, matrix.get_dimension());
+        assert_eq!(1, matrix.get(0, 1));
+        assert_eq!(5, matrix.get(2, 0));
+        assert_eq!(&[3, 0, 4], matrix.row(1));
+
+        matrix.set(1, 2, 7);
+
+        assert_eq!(7, matrix.get(1, 2));
+        assert_eq!(6, matrix.get(2, 1));
+        assert_eq!(
+            matrix,
+            DenseMatrix::from_fn(3, |from, to| matrix.get(from, to))
+        );
+    }
+
+    #[test]
+    fn packed_matrix_works() {
+        let dimension = 5;
+        let weight = |from: usize, to: usize| (10 * from.min(to) + from.max(to)) as u32;
+
+        let matrix = PackedMatrix::from_fn(dimension, weight);
+        let mut set_matrix = PackedMatrix::new(dimension);
+
+        for from in 0..dimension {
+            for to in 0..dimension {
+                assert_eq!(weight(from, to), matrix.get(from, to));
+            }
+
+            for to in 0..=from {
+                set_matrix.set(from, to, weight(from, to));
+            }
+        }
+
+        assert_eq!(matrix, set_matrix);
+        assert_eq!(15, matrix.weights.len());
+    }
+}
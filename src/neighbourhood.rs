@@ -11,6 +11,110 @@ pub fn swap(route: &mut [usize]) {
     route.swap(0, route.len() - 1)
 }
 
+/// Or-opt, moves the first `segment_len` cities behind the rest of the slice.
+/// Moving the last cities in front of the rest is the same move with
+/// `route.len() - segment_len`.
+pub fn move_segment(route: &mut [usize], segment_len: usize) {
+    route.rotate_left(segment_len)
+}
+
+/// 3-opt ways of joining the segments `B` and `C` of a slice, split after
+/// `B`, back into the route, see `reconnect`. The first three reverse a
+/// single segment and are 2-opt moves.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Reconnection {
+    /// `rev(B) C`
+    ReverseFirst,
+    /// `B rev(C)`
+    ReverseSecond,
+    /// `rev(C) rev(B)`, the whole slice inverted
+    ReverseWhole,
+    /// `rev(B) rev(C)`
+    ReverseBoth,
+    /// `C B`, the only case without reversal
+    Exchange,
+    /// `C rev(B)`
+    ExchangeReverseFirst,
+    /// `rev(C) B`
+    ExchangeReverseSecond,
+}
+
+impl Reconnection {
+    pub const ALL: [Reconnection; 7] = [
+        Reconnection::ReverseFirst,
+        Reconnection::ReverseSecond,
+        Reconnection::ReverseWhole,
+        Reconnection::ReverseBoth,
+        Reconnection::Exchange,
+        Reconnection::ExchangeReverseFirst,
+        Reconnection::ExchangeReverseSecond,
+    ];
+
+    pub fn reverses_first(self) -> bool {
+        matches!(
+            self,
+            Reconnection::ReverseFirst
+                | Reconnection::ReverseWhole
+                | Reconnection::ReverseBoth
+                | Reconnection::ExchangeReverseFirst
+        )
+    }
+
+    pub fn reverses_second(self) -> bool {
+        matches!(
+            self,
+            Reconnection::ReverseSecond
+                | Reconnection::ReverseWhole
+                | Reconnection::ReverseBoth
+                | Reconnection::ExchangeReverseSecond
+        )
+    }
+
+    /// Directed edges the reconnection adds, `ends` are the city before the
+    /// slice, the first and last city of `B`, of `C` and the city after it.
+    pub fn added_edges(self, ends: [usize; 6]) -> [(usize, usize); 3] {
+        let [before, b_first, b_last, c_first, c_last, after] = ends;
+
+        match self {
+            Reconnection::ReverseFirst => [(before, b_last), (b_first, c_first), (c_last, after)],
+            Reconnection::ReverseSecond => [(before, b_first), (b_last, c_last), (c_first, after)],
+            Reconnection::ReverseWhole => [(before, c_last), (c_first, b_last), (b_first, after)],
+            Reconnection::ReverseBoth => [(before, b_last), (b_first, c_last), (c_first, after)],
+            Reconnection::Exchange => [(before, c_first), (c_last, b_first), (b_last, after)],
+            Reconnection::ExchangeReverseFirst => {
+                [(before, c_first), (c_last, b_last), (b_first, after)]
+            }
+            Reconnection::ExchangeReverseSecond => {
+                [(before, c_last), (c_first, b_first), (b_last, after)]
+            }
+        }
+    }
+}
+
+/// 3-opt, `B` is `route[..split]` and `C` the rest of the slice.
+pub fn reconnect(route: &mut [usize], split: usize, reconnection: Reconnection) {
+    let (first, second) = route.split_at_mut(split);
+
+    if reconnection.reverses_first() {
+        invert(first);
+    }
+
+    if reconnection.reverses_second() {
+        invert(second);
+    }
+
+    // reversing both segments in place leaves them in the wrong order
+    if matches!(
+        reconnection,
+        Reconnection::ReverseWhole
+            | Reconnection::Exchange
+            | Reconnection::ExchangeReverseFirst
+            | Reconnection::ExchangeReverseSecond
+    ) {
+        route.rotate_left(split);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -50,4 +154,46 @@ mod tests {
 
         assert_eq!([1, 4, 3, 2, 5], route);
     }
+
+    #[test]
+    fn reconnect_works() {
+        for (reconnection, expected) in [
+            (Reconnection::ReverseFirst, [0, 3, 2, 1, 4, 5, 6]),
+            (Reconnection::ReverseSecond, [0, 1, 2, 3, 5, 4, 6]),
+            (Reconnection::ReverseWhole, [0, 5, 4, 3, 2, 1, 6]),
+            (Reconnection::ReverseBoth, [0, 3, 2, 1, 5, 4, 6]),
+            (Reconnection::Exchange, [0, 4, 5, 1, 2, 3, 6]),
+            (Reconnection::ExchangeReverseFirst, [0, 4, 5, 3, 2, 1, 6]),
+            (Reconnection::ExchangeReverseSecond, [0, 5, 4, 1, 2, 3, 6]),
+        ] {
+            let mut route = [0, 1, 2, 3, 4, 5, 6];
+
+            reconnect(&mut route[1..=5], 3, reconnection);
+
+            assert_eq!(expected, route, "{:?}", reconnection);
+
+            let added_edges = reconnection.added_edges([0, 1, 3, 4, 5, 6]);
+
+            for (from, to) in added_edges {
+                assert!(
+                    route.windows(2).any(|edge| edge == [from, to]),
+                    "{:?}",
+                    reconnection
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn move_segment_works() {
+        let mut route = [1, 2, 3, 4, 5, 6];
+
+        move_segment(&mut route[1..=4], 2);
+
+        assert_eq!([1, 4, 5, 2, 3, 6], route);
+
+        move_segment(&mut route[0..=5], 5);
+
+        assert_eq!([6, 1, 4, 5, 2, 3], route);
+    }
 }
@@ -0,0 +1,149 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::str::FromStr;
+
+use crate::tsp::{Tsp, TspRouteError};
+use crate::tsp_parser::{TspParser, TspParsingError};
+use crate::weight::Weight;
+
+/// Sequential ordering problem, an asymmetric `Tsp` whose route has to visit
+/// some vertices before others. `from_*` functions are shorthands for the
+/// default `u32` weights.
+#[derive(Debug, Clone)]
+pub struct Sop<W = u32> {
+    tsp: Tsp<W>,
+    precedences: Vec<(usize, usize)>,
+    predecessors: Vec<Vec<usize>>,
+}
+
+impl Sop {
+    pub fn from_file(filename: &str) -> Result<Sop, TspParsingError> {
+        Sop::parse_file(filename)
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Sop, TspParsingError> {
+        Sop::parse_reader(reader)
+    }
+}
+
+impl<W: Weight> Sop<W> {
+    /// Every precedence is a `(before, after)` pair of vertices.
+    pub fn new(tsp: Tsp<W>, precedences: Vec<(usize, usize)>) -> Sop<W> {
+        let mut predecessors = vec![Vec::new(); tsp.get_dimension()];
+
+        for &(before, after) in &precedences {
+            predecessors[after].push(before);
+        }
+
+        Sop {
+            tsp,
+            precedences,
+            predecessors,
+        }
+    }
+
+    pub fn parse_file(filename: &str) -> Result<Sop<W>, TspParsingError> {
+        let file = File::open(filename).map_err(TspParsingError::FileDoesntExist)?;
+
+        Sop::parse_reader(BufReader::new(file))
+    }
+
+    pub fn parse_reader<R: BufRead>(reader: R) -> Result<Sop<W>, TspParsingError> {
+        let (tsp, extra_sections) = TspParser::parse_with_extra_sections(reader)?;
+
+        Ok(Sop::new(tsp, extra_sections.precedences))
+    }
+
+    pub fn get_route_len(&self, route: &[usize]) -> Result<W::Length, TspRouteError> {
+        self.check_route_feasible(route)?;
+
+        self.tsp.get_route_len(route)
+    }
+
+    /// Like `Tsp::check_route_valid`, but also checks every precedence.
+    pub fn check_route_feasible(&self, route: &[usize]) -> Result<(), TspRouteError> {
+        self.tsp.check_route_valid(route)?;
+
+        let mut positions = vec![0; route.len()];
+
+        for (i, &vertex) in route.iter().enumerate() {
+            positions[vertex] = i;
+        }
+
+        for &(before, after) in &self.precedences {
+            if positions[before] > positions[after] {
+                return Err(TspRouteError::PrecedenceViolated(before, after));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get_tsp(&self) -> &Tsp<W> {
+        &self.tsp
+    }
+
+    pub fn get_precedences(&self) -> &[(usize, usize)] {
+        &self.precedences
+    }
+
+    pub fn get_predecessors(&self, vertex: usize) -> &[usize] {
+        &self.predecessors[vertex]
+    }
+}
+
+impl<W: Weight> FromStr for Sop<W> {
+    type Err = TspParsingError;
+
+    fn from_str(content: &str) -> Result<Sop<W>, TspParsingError> {
+        Sop::parse_reader(content.as_bytes())
+    }
+}
+
+pub trait SopHeuristic<W: Weight = u32> {
+    fn get_route(&self, sop: &Sop<W>) -> Vec<usize>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ProblemType, TspType};
+
+    #[test]
+    fn sop_file_works() {
+        let sop = Sop::from_file("sop").expect("Couldn't parse file");
+
+        assert_eq!(
+            Some(ProblemType::Sop),
+            sop.get_tsp().get_header().problem_type
+        );
+        assert!(matches!(sop.get_tsp().get_tsp_type(), TspType::Asymmetric));
+        assert_eq!(7, sop.get_tsp().get_dimension());
+        assert_eq!(&[0, 2], sop.get_predecessors(1));
+        assert_eq!(0, sop.get_tsp().get_edge(1, 0));
+        assert_eq!(1000000, sop.get_tsp().get_edge(0, 6));
+    }
+
+    #[test]
+    fn feasibility_works() {
+        let sop = Sop::from_file("sop").expect("Couldn't parse file");
+
+        assert_eq!(46, sop.get_route_len(&[0, 2, 4, 3, 1, 5, 6]).unwrap());
+        assert!(matches!(
+            sop.check_route_feasible(&[0, 1, 2, 3, 4, 5, 6]),
+            Err(TspRouteError::PrecedenceViolated(2, 1))
+        ));
+        assert!(matches!(
+            sop.check_route_feasible(&[0, 2, 4, 3, 1, 5]),
+            Err(TspRouteError::TooShort)
+        ));
+    }
+
+    #[test]
+    fn other_weights_work() {
+        let content = std::fs::read_to_string("sop").expect("file doesn't exist");
+        let sop = content.parse::<Sop<u64>>().expect("Couldn't parse file");
+
+        assert_eq!(46, sop.get_route_len(&[0, 2, 4, 3, 1, 5, 6]).unwrap());
+    }
+}
@@ -0,0 +1,238 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::str::FromStr;
+
+use crate::tsp::{Tsp, TspRouteError};
+use crate::tsp_parser::tokens::Tokens;
+use crate::tsp_parser::{ProblemType, TspHeader, TspParsingError};
+use crate::weight::Weight;
+
+/// Route stored in a TSPLIB `TYPE: TOUR` file, vertices are 0-based like
+/// everywhere else in the crate and only 1-based inside the file.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Tour {
+    header: TspHeader,
+    route: Vec<usize>,
+}
+
+impl Tour {
+    pub fn new(route: Vec<usize>) -> Tour {
+        Tour {
+            header: TspHeader {
+                problem_type: Some(ProblemType::Tour),
+                dimension: route.len(),
+                ..TspHeader::default()
+            },
+            route,
+        }
+    }
+
+    pub fn with_header(mut self, header: TspHeader) -> Tour {
+        self.header = header;
+        self
+    }
+
+    pub fn from_file(filename: &str) -> Result<Tour, TspParsingError> {
+        let file = File::open(filename).map_err(TspParsingError::FileDoesntExist)?;
+
+        Tour::from_reader(BufReader::new(file))
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Tour, TspParsingError> {
+        let mut tokens = Tokens::new(reader);
+
+        let (header, mut section) = TspHeader::parse(&mut tokens)?;
+
+        while section != "TOUR_SECTION" {
+            section = tokens
+                .next_line()?
+                .ok_or(TspParsingError::NoTourSection)?
+                .trim()
+                .to_string();
+        }
+
+        tokens.set_section(&section);
+
+        let mut route = Vec::with_capacity(header.dimension);
+
+        loop {
+            let context = tokens.peek_context()?;
+
+            match tokens.next_number::<i64, _>(TspParsingError::NodeNotANumber)? {
+                None | Some(-1) => break,
+                Some(node) if node >= 1 => route.push(node as usize - 1),
+                Some(_) => {
+                    return Err(TspParsingError::NodeOutOfRange(
+                        context.expect("number was just read"),
+                    ))
+                }
+            }
+        }
+
+        Ok(Tour { header, route })
+    }
+
+    pub fn check<W: Weight>(&self, tsp: &Tsp<W>) -> Result<(), TspRouteError> {
+        tsp.check_route_valid(&self.route)
+    }
+
+    pub fn to_file(&self, filename: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(filename)?);
+
+        self.to_writer(&mut writer)?;
+
+        writer.flush()
+    }
+
+    pub fn to_writer<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(writer, "{}", self)
+    }
+
+    pub fn get_route(&self) -> &[usize] {
+        &self.route
+    }
+
+    pub fn get_header(&self) -> &TspHeader {
+        &self.header
+    }
+
+    pub fn into_route(self) -> Vec<usize> {
+        self.route
+    }
+}
+
+impl fmt::Display for Tour {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(name) = &self.header.name {
+            writeln!(f, "NAME: {}", name)?;
+        }
+
+        writeln!(f, "TYPE: {}", ProblemType::Tour.keyword())?;
+
+        for comment in self
+            .header
+            .comment
+            .iter()
+            .flat_map(|comment| comment.lines())
+        {
+            writeln!(f, "COMMENT: {}", comment)?;
+        }
+
+        writeln!(f, "DIMENSION: {}", self.route.len())?;
+        writeln!(f, "TOUR_SECTION")?;
+
+        for vertex in &self.route {
+            writeln!(f, "{}", vertex + 1)?;
+        }
+
+        writeln!(f, "-1")?;
+        writeln!(f, "EOF")
+    }
+}
+
+impl FromStr for Tour {
+    type Err = TspParsingError;
+
+    fn from_str(content: &str) -> Result<Tour, TspParsingError> {
+        Tour::from_reader(content.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NearestNeighbour, TspHeuristic, TspParser};
+
+    fn check_optimal_tour(tsp_file: &str, tour_file: &str, optimal_route_len: u64) {
+        let tsp = TspParser::from_file(tsp_file).expect("Couldn't parse file");
+        let tour = Tour::from_file(tour_file).expect("Couldn't parse tour");
+
+        tour.check(&tsp).expect("Optimal tour has to be valid");
+
+        assert_eq!(Some(ProblemType::Tour), tour.get_header().problem_type);
+        assert_eq!(
+            optimal_route_len,
+            tsp.get_route_len(tour.get_route()).unwrap()
+        );
+    }
+
+    #[test]
+    fn optimal_tours_work() {
+        check_optimal_tour(
+            "test_files/ulysses16.tsp",
+            "test_files/ulysses16.opt.tour",
+            6859,
+        );
+        check_optimal_tour("test_files/att48.tsp", "test_files/att48.opt.tour", 10628);
+    }
+
+    #[test]
+    fn round_trip_works() {
+        let tsp = TspParser::from_file("test_files/berlin52.tsp").expect("Couldn't parse file");
+        let route = NearestNeighbour.get_route(&tsp);
+
+        let tour = Tour::new(route.clone()).with_header(TspHeader {
+            name: Some(String::from("berlin52.tour")),
+            comment: Some(String::from("nearest neighbour")),
+            ..TspHeader::default()
+        });
+
+        let parsed: Tour = tour.to_string().parse().expect("Couldn't parse tour");
+
+        assert_eq!(route, parsed.get_route());
+        assert_eq!(Some("berlin52.tour"), parsed.get_header().name.as_deref());
+        assert_eq!(52, parsed.get_header().dimension);
+        parsed.check(&tsp).expect("Has to be valid route");
+    }
+
+    #[test]
+    fn writer_output() {
+        let tour = Tour::new(vec![2, 0, 1]);
+
+        assert_eq!(
+            "TYPE: TOUR\nDIMENSION: 3\nTOUR_SECTION\n3\n1\n2\n-1\nEOF\n",
+            tour.to_string()
+        );
+    }
+
+    #[test]
+    fn tour_without_terminator() {
+        let tour: Tour = "DIMENSION: 3\nTOUR_SECTION\n1 3 2\n"
+            .parse()
+            .expect("Couldn't parse tour");
+
+        assert_eq!(&[0, 2, 1], tour.get_route());
+    }
+
+    #[test]
+    fn zero_node_is_located() {
+        let error = "DIMENSION: 3\nTOUR_SECTION\n1\n0\n2\n-1\n"
+            .parse::<Tour>()
+            .unwrap_err();
+
+        match error {
+            TspParsingError::NodeOutOfRange(context) => {
+                assert_eq!(4, context.line);
+                assert_eq!("0", context.token);
+                assert_eq!("TOUR_SECTION", context.section);
+            }
+            error => panic!("unexpected error: {:?}", error),
+        }
+    }
+
+    #[test]
+    fn missing_tour_section() {
+        let error = "DIMENSION: 3\nEOF\n".parse::<Tour>().unwrap_err();
+
+        assert!(matches!(error, TspParsingError::NoTourSection));
+    }
+
+    #[test]
+    fn tour_of_other_instance_is_invalid() {
+        let tsp = TspParser::from_file("test_files/berlin52.tsp").expect("Couldn't parse file");
+        let tour = Tour::from_file("test_files/att48.opt.tour").expect("Couldn't parse tour");
+
+        assert!(matches!(tour.check(&tsp), Err(TspRouteError::TooShort)));
+    }
+}
@@ -1,71 +1,254 @@
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use crate::candidates::{CandidateLists, CandidateSource, DEFAULT_CANDIDATES};
+use crate::distances::Distances;
+use crate::neighbourhood::Reconnection;
+use crate::tsp_analysis::TspAnalysis;
+use crate::tsp_parser::TspHeader;
+use crate::weight::Weight;
+
 #[derive(Debug)]
 pub enum TspRouteError {
     TooShort,
     NotPermutation,
+    MissingFixedEdge(usize, usize),
+    PrecedenceViolated(usize, usize),
+    MissingEdge(usize, usize),
+}
+
+impl std::fmt::Display for TspRouteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TspRouteError::TooShort => write!(f, "route doesn't visit every vertex"),
+            TspRouteError::NotPermutation => write!(f, "route isn't a permutation of vertices"),
+            TspRouteError::MissingFixedEdge(from, to) => {
+                write!(f, "route doesn't contain fixed edge ({}, {})", from, to)
+            }
+            TspRouteError::PrecedenceViolated(before, after) => {
+                write!(f, "route visits {} before {}", after, before)
+            }
+            TspRouteError::MissingEdge(from, to) => {
+                write!(
+                    f,
+                    "route uses edge ({}, {}) missing from the graph",
+                    from, to
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TspRouteError {}
+
+/// Fixed edges no route can contain all at once.
+#[derive(Debug, PartialEq)]
+pub enum FixedEdgesError {
+    VertexOutOfRange(usize),
+    /// More than two fixed edges at the vertex, or more than one leaving or
+    /// entering it for asymmetric instances.
+    TooManyEdges(usize),
+    /// Fixed edges close a cycle through the vertex which doesn't visit
+    /// every vertex.
+    ShortCycle(usize),
+}
+
+impl std::fmt::Display for FixedEdgesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FixedEdgesError::VertexOutOfRange(vertex) => {
+                write!(f, "fixed edge vertex {} doesn't exist", vertex)
+            }
+            FixedEdgesError::TooManyEdges(vertex) => {
+                write!(f, "vertex {} has too many fixed edges", vertex)
+            }
+            FixedEdgesError::ShortCycle(vertex) => {
+                write!(f, "fixed edges close a short cycle through {}", vertex)
+            }
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+impl std::error::Error for FixedEdgesError {}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum TspType {
     Symmetric,
     Asymmetric,
 }
 
 #[derive(Debug, Clone)]
-pub struct Tsp {
-    edges: Vec<Vec<u32>>,
+pub struct Tsp<W = u32> {
+    distances: Distances<W>,
     dimension: usize,
     tsp_type: TspType,
+    header: TspHeader,
+    display_data: Option<Vec<(f64, f64)>>,
+    fixed_edges: Vec<(usize, usize)>,
+    fixed_edges_lookup: HashSet<(usize, usize)>,
+    candidate_source: CandidateSource,
+    candidates: OnceLock<CandidateLists>,
 }
 
-impl Tsp {
-    pub fn new(edges: Vec<Vec<u32>>, dimension: usize, tsp_type: TspType) -> Tsp {
+impl<W: Weight> Tsp<W> {
+    pub fn new(distances: Distances<W>, dimension: usize, tsp_type: TspType) -> Tsp<W> {
         Tsp {
-            edges,
+            distances,
             dimension,
             tsp_type,
+            header: TspHeader {
+                dimension,
+                ..TspHeader::default()
+            },
+            display_data: None,
+            fixed_edges: Vec::new(),
+            fixed_edges_lookup: HashSet::new(),
+            candidate_source: CandidateSource::Quadrant(DEFAULT_CANDIDATES),
+            candidates: OnceLock::new(),
+        }
+    }
+
+    pub fn with_header(mut self, header: TspHeader) -> Tsp<W> {
+        self.header = header;
+        self
+    }
+
+    /// Positions of nodes which don't define the edge weights, like the ones
+    /// from DISPLAY_DATA_SECTION of explicit instances.
+    pub fn with_display_data(mut self, display_data: Vec<(f64, f64)>) -> Tsp<W> {
+        self.display_data = Some(display_data);
+        self
+    }
+
+    /// Edges every route has to contain, directed for asymmetric instances.
+    /// Repeated edges are dropped, edges no route can contain all at once
+    /// are an error.
+    pub fn with_fixed_edges(
+        mut self,
+        fixed_edges: Vec<(usize, usize)>,
+    ) -> Result<Tsp<W>, FixedEdgesError> {
+        self.fixed_edges_lookup = HashSet::new();
+        self.fixed_edges = Vec::with_capacity(fixed_edges.len());
+
+        for (from, to) in fixed_edges {
+            if let Some(&vertex) = [from, to].iter().find(|&&v| v >= self.dimension) {
+                return Err(FixedEdgesError::VertexOutOfRange(vertex));
+            }
+
+            let key = self.fixed_edge_key(from, to);
+
+            if self.fixed_edges_lookup.insert(key) {
+                self.fixed_edges.push((from, to));
+            }
+        }
+
+        self.check_fixed_edges()?;
+
+        Ok(self)
+    }
+
+    // fixed edges have to form paths, or a single cycle through all vertices
+    fn check_fixed_edges(&self) -> Result<(), FixedEdgesError> {
+        let mut edge_counts = vec![[0; 2]; self.dimension];
+
+        // the other end and size of every path, kept at both of its ends
+        let mut other_end: Vec<_> = (0..self.dimension).collect();
+        let mut path_len = vec![1; self.dimension];
+
+        for &(from, to) in &self.fixed_edges {
+            edge_counts[from][0] += 1;
+            edge_counts[to][1] += 1;
+
+            for vertex in [from, to] {
+                let [leaving, entering] = edge_counts[vertex];
+
+                let is_full = match self.tsp_type {
+                    TspType::Symmetric => leaving + entering > 2,
+                    TspType::Asymmetric => leaving > 1 || entering > 1,
+                };
+
+                if is_full {
+                    return Err(FixedEdgesError::TooManyEdges(vertex));
+                }
+            }
+
+            // both vertices are ends of their paths, joining the two ends of
+            // one path closes a cycle
+            if other_end[from] == to {
+                if path_len[from] < self.dimension {
+                    return Err(FixedEdgesError::ShortCycle(from));
+                }
+
+                continue;
+            }
+
+            let (first, last) = (other_end[from], other_end[to]);
+            let len = path_len[from] + path_len[to];
+
+            other_end[first] = last;
+            other_end[last] = first;
+            path_len[first] = len;
+            path_len[last] = len;
         }
+
+        Ok(())
     }
 
-    pub fn get_route_len(&self, route: &[usize]) -> Result<u32, TspRouteError> {
+    /// Changes how candidate lists are made, quadrant neighbours by default.
+    pub fn with_candidates(mut self, source: CandidateSource) -> Tsp<W> {
+        self.candidate_source = source;
+        self.candidates = OnceLock::new();
+        self
+    }
+
+    /// Report on symmetry, the triangle inequality, the diagonal and weights,
+    /// in cubic time.
+    pub fn analyze(&self) -> TspAnalysis<W> {
+        TspAnalysis::new(self)
+    }
+
+    pub fn get_route_len(&self, route: &[usize]) -> Result<W::Length, TspRouteError> {
         self.check_route_valid(route)?;
 
-        let mut route_len = 0;
+        let mut route_len = W::Length::default();
 
         for i in 0..self.dimension - 1 {
             let first_vertex = route[i];
             let second_vertex = route[i + 1];
 
-            route_len += self.edges[first_vertex][second_vertex]
+            route_len += self.edge_len(first_vertex, second_vertex)
         }
 
         let first_vertex = route[0];
         let last_vertex = route[route.len() - 1];
 
-        route_len += self.edges[last_vertex][first_vertex];
+        route_len += self.edge_len(last_vertex, first_vertex);
 
         Ok(route_len)
     }
 
+    #[inline]
     pub fn get_inverted_route_len(
         &self,
         route: &[usize],
-        route_len: u32,
+        route_len: W::Length,
         i: usize,
         j: usize,
-    ) -> u32 {
+    ) -> W::Length {
         match self.tsp_type {
             TspType::Symmetric => self.get_inverted_symmetric_route_len(route, route_len, i, j),
             TspType::Asymmetric => self.get_inverted_asymmetric_route_len(route, route_len, i, j),
         }
     }
 
+    #[inline]
     pub fn get_inverted_symmetric_route_len(
         &self,
         route: &[usize],
-        mut route_len: u32,
+        mut route_len: W::Length,
         i: usize,
         j: usize,
-    ) -> u32 {
+    ) -> W::Length {
         if i == 0 && j == self.dimension - 1 {
             return route_len;
         }
@@ -73,11 +256,11 @@ impl Tsp {
         let before_index = if i == 0 { self.dimension - 1 } else { i - 1 };
         let after_index = if j == self.dimension - 1 { 0 } else { j + 1 };
 
-        route_len += self.edges[route[i]][route[after_index]];
-        route_len += self.edges[route[before_index]][route[j]];
+        route_len += self.edge_len(route[i], route[after_index]);
+        route_len += self.edge_len(route[before_index], route[j]);
 
-        route_len -= self.edges[route[before_index]][route[i]];
-        route_len -= self.edges[route[j]][route[after_index]];
+        route_len -= self.edge_len(route[before_index], route[i]);
+        route_len -= self.edge_len(route[j], route[after_index]);
 
         route_len
     }
@@ -85,30 +268,114 @@ impl Tsp {
     pub fn get_swap_route_len(
         &self,
         route: &[usize],
-        mut route_len: u32,
+        mut route_len: W::Length,
         i: usize,
         j: usize,
-    ) -> u32 {
+    ) -> W::Length {
         let before_i_index = if i == 0 { self.dimension - 1 } else { i - 1 };
         let after_i_index = i + 1;
         let before_j_index = j - 1;
         let after_j_index = if j == self.dimension - 1 { 0 } else { j + 1 };
 
-        route_len -= self.edges[route[before_i_index]][route[i]];
-        route_len -= self.edges[route[i]][route[after_i_index]];
-        route_len -= self.edges[route[j]][route[after_j_index]];
+        route_len -= self.edge_len(route[before_i_index], route[i]);
+        route_len -= self.edge_len(route[i], route[after_i_index]);
+        route_len -= self.edge_len(route[j], route[after_j_index]);
 
         if after_i_index != j {
-            route_len -= self.edges[route[before_j_index]][route[j]];
+            route_len -= self.edge_len(route[before_j_index], route[j]);
 
-            route_len += self.edges[route[before_j_index]][route[i]];
-            route_len += self.edges[route[j]][route[after_i_index]];
+            route_len += self.edge_len(route[before_j_index], route[i]);
+            route_len += self.edge_len(route[j], route[after_i_index]);
         } else {
-            route_len += self.edges[route[j]][route[i]];
+            route_len += self.edge_len(route[j], route[i]);
         }
 
-        route_len += self.edges[route[before_i_index]][route[j]];
-        route_len += self.edges[route[i]][route[after_j_index]];
+        route_len += self.edge_len(route[before_i_index], route[j]);
+        route_len += self.edge_len(route[i], route[after_j_index]);
+
+        route_len
+    }
+
+    /// Length after `neighbourhood::move_segment(&mut route[i..=j], segment_len)`,
+    /// the moved cities keep their order, so it works for both `TspType`s.
+    /// The slice wraps around the end of the route when `j < i`.
+    #[inline]
+    pub fn get_moved_segment_route_len(
+        &self,
+        route: &[usize],
+        mut route_len: W::Length,
+        i: usize,
+        j: usize,
+        segment_len: usize,
+    ) -> W::Length {
+        if (j + 1) % self.dimension == i {
+            return route_len;
+        }
+
+        let before_index = (i + self.dimension - 1) % self.dimension;
+        let after_index = (j + 1) % self.dimension;
+        let split_index = (i + segment_len) % self.dimension;
+        let segment_end_index = (split_index + self.dimension - 1) % self.dimension;
+
+        route_len += self.edge_len(route[before_index], route[split_index]);
+        route_len += self.edge_len(route[j], route[i]);
+        route_len += self.edge_len(route[segment_end_index], route[after_index]);
+
+        route_len -= self.edge_len(route[before_index], route[i]);
+        route_len -= self.edge_len(route[segment_end_index], route[split_index]);
+        route_len -= self.edge_len(route[j], route[after_index]);
+
+        route_len
+    }
+
+    /// Length after `neighbourhood::reconnect(&mut route[i..=k], j - i, reconnection)`,
+    /// `i < j <= k` and the slice can't be the whole route. Constant time,
+    /// except for reversals on asymmetric instances.
+    pub fn get_reconnected_route_len(
+        &self,
+        route: &[usize],
+        mut route_len: W::Length,
+        i: usize,
+        j: usize,
+        k: usize,
+        reconnection: Reconnection,
+    ) -> W::Length {
+        debug_assert!(
+            i != 0 || k != self.dimension - 1,
+            "slice is the whole route"
+        );
+
+        let before_index = if i == 0 { self.dimension - 1 } else { i - 1 };
+        let after_index = if k == self.dimension - 1 { 0 } else { k + 1 };
+
+        let ends = [
+            route[before_index],
+            route[i],
+            route[j - 1],
+            route[j],
+            route[k],
+            route[after_index],
+        ];
+
+        for (from, to) in reconnection.added_edges(ends) {
+            route_len += self.edge_len(from, to);
+        }
+
+        route_len -= self.edge_len(route[before_index], route[i]);
+        route_len -= self.edge_len(route[j - 1], route[j]);
+        route_len -= self.edge_len(route[k], route[after_index]);
+
+        if let TspType::Asymmetric = self.tsp_type {
+            if reconnection.reverses_first() {
+                route_len -= self.get_part_route_len(&route[i..j]);
+                route_len += self.get_inverted_part_route_len(&route[i..j]);
+            }
+
+            if reconnection.reverses_second() {
+                route_len -= self.get_part_route_len(&route[j..=k]);
+                route_len += self.get_inverted_part_route_len(&route[j..=k]);
+            }
+        }
 
         route_len
     }
@@ -118,18 +385,18 @@ impl Tsp {
     pub fn get_inverted_asymmetric_route_len(
         &self,
         route: &[usize],
-        mut route_len: u32,
+        mut route_len: W::Length,
         i: usize,
         j: usize,
-    ) -> u32 {
+    ) -> W::Length {
         let before_index = if i == 0 { self.dimension - 1 } else { i - 1 };
         let after_index = if j == self.dimension - 1 { 0 } else { j + 1 };
 
-        route_len += self.edges[route[i]][route[after_index]];
-        route_len += self.edges[route[before_index]][route[j]];
+        route_len += self.edge_len(route[i], route[after_index]);
+        route_len += self.edge_len(route[before_index], route[j]);
 
-        route_len -= self.edges[route[before_index]][route[i]];
-        route_len -= self.edges[route[j]][route[after_index]];
+        route_len -= self.edge_len(route[before_index], route[i]);
+        route_len -= self.edge_len(route[j], route[after_index]);
 
         route_len -= self.get_part_route_len(&route[i..=j]);
         route_len += self.get_inverted_part_route_len(&route[i..=j]);
@@ -137,27 +404,27 @@ impl Tsp {
         route_len
     }
 
-    fn get_part_route_len(&self, route: &[usize]) -> u32 {
-        let mut route_len = 0;
+    fn get_part_route_len(&self, route: &[usize]) -> W::Length {
+        let mut route_len = W::Length::default();
 
         for i in 0..route.len() - 1 {
             let first_vertex = route[i];
             let second_vertex = route[i + 1];
 
-            route_len += self.edges[first_vertex][second_vertex];
+            route_len += self.edge_len(first_vertex, second_vertex);
         }
 
         route_len
     }
 
-    fn get_inverted_part_route_len(&self, route: &[usize]) -> u32 {
-        let mut route_len = 0;
+    fn get_inverted_part_route_len(&self, route: &[usize]) -> W::Length {
+        let mut route_len = W::Length::default();
 
         for i in (0..route.len() - 1).rev() {
             let first_vertex = route[i + 1];
             let second_vertex = route[i];
 
-            route_len += self.edges[first_vertex][second_vertex];
+            route_len += self.edge_len(first_vertex, second_vertex);
         }
 
         route_len
@@ -165,24 +432,226 @@ impl Tsp {
 
     // ===
 
-    fn check_route_valid(&self, route: &[usize]) -> Result<(), TspRouteError> {
-        if route.len() != self.dimension {
-            return Err(TspRouteError::TooShort);
+    /// Checks that the route visits every vertex exactly once and contains
+    /// every fixed edge.
+    pub fn check_route_valid(&self, route: &[usize]) -> Result<(), TspRouteError> {
+        check_permutation(route, self.dimension)?;
+
+        if self.fixed_edges.is_empty() {
+            return Ok(());
+        }
+
+        let mut positions = vec![0; self.dimension];
+
+        for (i, &vertex) in route.iter().enumerate() {
+            positions[vertex] = i;
         }
 
-        let mut route_clone = route.to_vec();
+        let next_vertex = |vertex: usize| route[(positions[vertex] + 1) % self.dimension];
 
-        route_clone.sort_unstable();
+        for &(from, to) in &self.fixed_edges {
+            let contained = match self.tsp_type {
+                TspType::Symmetric => next_vertex(from) == to || next_vertex(to) == from,
+                TspType::Asymmetric => next_vertex(from) == to,
+            };
 
-        if route_clone != (0..self.dimension).collect::<Vec<_>>() {
-            return Err(TspRouteError::NotPermutation);
+            if !contained {
+                return Err(TspRouteError::MissingFixedEdge(from, to));
+            }
         }
 
         Ok(())
     }
 
-    pub fn get_edges(&self) -> &[Vec<u32>] {
-        &self.edges
+    pub fn get_fixed_edges(&self) -> &[(usize, usize)] {
+        &self.fixed_edges
+    }
+
+    #[inline]
+    pub fn is_edge_fixed(&self, from: usize, to: usize) -> bool {
+        !self.fixed_edges.is_empty()
+            && self
+                .fixed_edges_lookup
+                .contains(&self.fixed_edge_key(from, to))
+    }
+
+    /// Whether inverting `route[i..=j]` keeps every fixed edge, the asymmetric
+    /// inversion also reverses all edges inside the segment.
+    pub fn is_inversion_allowed(&self, route: &[usize], i: usize, j: usize) -> bool {
+        if self.fixed_edges.is_empty() || i == j || (i == 0 && j == self.dimension - 1) {
+            return true;
+        }
+
+        let before_index = if i == 0 { self.dimension - 1 } else { i - 1 };
+        let after_index = if j == self.dimension - 1 { 0 } else { j + 1 };
+
+        if self.is_edge_fixed(route[before_index], route[i])
+            || self.is_edge_fixed(route[j], route[after_index])
+        {
+            return false;
+        }
+
+        match self.tsp_type {
+            TspType::Symmetric => true,
+            TspType::Asymmetric => (i..j).all(|k| !self.is_edge_fixed(route[k], route[k + 1])),
+        }
+    }
+
+    /// Whether swapping `route[i]` with `route[j]`, `i < j`, keeps every fixed edge.
+    pub fn is_swap_allowed(&self, route: &[usize], i: usize, j: usize) -> bool {
+        if self.fixed_edges.is_empty() {
+            return true;
+        }
+
+        let before_i_index = if i == 0 { self.dimension - 1 } else { i - 1 };
+        let after_j_index = if j == self.dimension - 1 { 0 } else { j + 1 };
+
+        !(self.is_edge_fixed(route[before_i_index], route[i])
+            || self.is_edge_fixed(route[i], route[i + 1])
+            || self.is_edge_fixed(route[j - 1], route[j])
+            || self.is_edge_fixed(route[j], route[after_j_index]))
+    }
+
+    /// Whether `neighbourhood::move_segment(&mut route[i..=j], segment_len)`
+    /// keeps every fixed edge, the slice wraps around when `j < i`.
+    pub fn is_segment_move_allowed(
+        &self,
+        route: &[usize],
+        i: usize,
+        j: usize,
+        segment_len: usize,
+    ) -> bool {
+        if self.fixed_edges.is_empty() || (j + 1) % self.dimension == i {
+            return true;
+        }
+
+        let before_index = (i + self.dimension - 1) % self.dimension;
+        let after_index = (j + 1) % self.dimension;
+        let split_index = (i + segment_len) % self.dimension;
+        let segment_end_index = (split_index + self.dimension - 1) % self.dimension;
+
+        !(self.is_edge_fixed(route[before_index], route[i])
+            || self.is_edge_fixed(route[segment_end_index], route[split_index])
+            || self.is_edge_fixed(route[j], route[after_index]))
+    }
+
+    /// Whether `neighbourhood::reconnect(&mut route[i..=k], j - i, reconnection)`
+    /// keeps every fixed edge.
+    pub fn is_reconnection_allowed(
+        &self,
+        route: &[usize],
+        i: usize,
+        j: usize,
+        k: usize,
+        reconnection: Reconnection,
+    ) -> bool {
+        if self.fixed_edges.is_empty() {
+            return true;
+        }
+
+        let before_index = if i == 0 { self.dimension - 1 } else { i - 1 };
+        let after_index = if k == self.dimension - 1 { 0 } else { k + 1 };
+
+        if self.is_edge_fixed(route[before_index], route[i])
+            || self.is_edge_fixed(route[j - 1], route[j])
+            || self.is_edge_fixed(route[k], route[after_index])
+        {
+            return false;
+        }
+
+        let is_reversal_allowed = |segment: &[usize]| {
+            segment
+                .windows(2)
+                .all(|edge| !self.is_edge_fixed(edge[0], edge[1]))
+        };
+
+        match self.tsp_type {
+            TspType::Symmetric => true,
+            TspType::Asymmetric => {
+                (!reconnection.reverses_first() || is_reversal_allowed(&route[i..j]))
+                    && (!reconnection.reverses_second() || is_reversal_allowed(&route[j..=k]))
+            }
+        }
+    }
+
+    /// Splits vertices into paths glued by fixed edges, every vertex without
+    /// a fixed edge is a path on its own. Heuristics building routes from
+    /// scratch can then treat every path as a single vertex.
+    pub fn fixed_paths(&self) -> Vec<Vec<usize>> {
+        let mut next = vec![Vec::new(); self.dimension];
+        let mut has_previous = vec![false; self.dimension];
+
+        for &(from, to) in &self.fixed_edges {
+            next[from].push(to);
+            has_previous[to] = true;
+
+            if let TspType::Symmetric = self.tsp_type {
+                next[to].push(from);
+            }
+        }
+
+        let is_path_start = |vertex: usize| match self.tsp_type {
+            TspType::Symmetric => next[vertex].len() < 2,
+            TspType::Asymmetric => !has_previous[vertex],
+        };
+
+        let mut visited = vec![false; self.dimension];
+        let mut paths = Vec::new();
+
+        // vertices left after the first pass lie on fixed cycles
+        for first_pass in [true, false] {
+            for start in 0..self.dimension {
+                if visited[start] || (first_pass && !is_path_start(start)) {
+                    continue;
+                }
+
+                let mut path = vec![start];
+                let mut curr_vertex = start;
+                visited[start] = true;
+
+                while let Some(&next_vertex) = next[curr_vertex].iter().find(|&&v| !visited[v]) {
+                    path.push(next_vertex);
+                    visited[next_vertex] = true;
+                    curr_vertex = next_vertex;
+                }
+
+                paths.push(path);
+            }
+        }
+
+        paths
+    }
+
+    fn fixed_edge_key(&self, from: usize, to: usize) -> (usize, usize) {
+        match self.tsp_type {
+            TspType::Symmetric => (from.min(to), from.max(to)),
+            TspType::Asymmetric => (from, to),
+        }
+    }
+
+    #[inline]
+    pub fn get_edge(&self, from: usize, to: usize) -> W {
+        self.distances.get(from, to)
+    }
+
+    #[inline]
+    fn edge_len(&self, from: usize, to: usize) -> W::Length {
+        self.get_edge(from, to).into()
+    }
+
+    /// Cities to try new edges to from every city, built on the first call.
+    /// Takes quadratic time unless the instance gets a `KdTree`.
+    pub fn get_candidates(&self) -> &CandidateLists {
+        self.candidates
+            .get_or_init(|| CandidateLists::new(self, &self.candidate_source))
+    }
+
+    pub fn get_candidate_source(&self) -> &CandidateSource {
+        &self.candidate_source
+    }
+
+    pub fn get_distances(&self) -> &Distances<W> {
+        &self.distances
     }
 
     pub fn get_dimension(&self) -> usize {
@@ -192,12 +661,44 @@ impl Tsp {
     pub fn get_tsp_type(&self) -> TspType {
         self.tsp_type
     }
+
+    pub fn get_header(&self) -> &TspHeader {
+        &self.header
+    }
+
+    /// Node positions from NODE_COORD_SECTION or DISPLAY_DATA_SECTION, if
+    /// the instance has any in two dimensions.
+    pub fn coordinates(&self) -> Option<&[(f64, f64)]> {
+        match &self.distances {
+            Distances::Coordinates(coords, _) => Some(coords),
+            _ => self.display_data.as_deref(),
+        }
+    }
+}
+
+pub(crate) fn check_permutation(route: &[usize], dimension: usize) -> Result<(), TspRouteError> {
+    if route.len() != dimension {
+        return Err(TspRouteError::TooShort);
+    }
+
+    let mut route_clone = route.to_vec();
+
+    route_clone.sort_unstable();
+
+    if route_clone != (0..dimension).collect::<Vec<_>>() {
+        return Err(TspRouteError::NotPermutation);
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::neighbourhood::{invert, swap};
-    use crate::{Tsp, TspParser};
+    use crate::neighbourhood::{invert, move_segment, reconnect, swap, Reconnection};
+    use crate::{
+        CandidateSource, DenseMatrix, Distances, FixedEdgesError, NearestNeighbourOptimized, Tsp,
+        TspHeuristic, TspParser, TspRouteError, TspType,
+    };
 
     #[test]
     fn different_route_lens() {
@@ -243,4 +744,196 @@ mod tests {
         check_swap(&tsp, 50, 60);
         check_swap(&tsp, 45, 55);
     }
+
+    #[test]
+    fn nearest_neighbour_skips_other_candidates() {
+        let tsp = TspParser::from_file("test_files/ulysses16.tsp").expect("Couldn't parse file");
+
+        NearestNeighbourOptimized::new().get_route(&tsp);
+
+        // GEO instances get quadrant lists, which nearest neighbour can't use
+        assert!(tsp.candidates.get().is_none());
+
+        let tsp = tsp.with_candidates(CandidateSource::Nearest(3));
+
+        NearestNeighbourOptimized::new().get_route(&tsp);
+
+        assert!(tsp.candidates.get().is_some());
+    }
+
+    #[test]
+    fn moved_segment_route_lens() {
+        for filename in ["test_files/berlin52.tsp", "test_files/ft70.atsp"] {
+            let tsp = TspParser::from_file(filename).expect("test file doesnt exist");
+            let dimension = tsp.dimension;
+            let moves = [
+                (0, 5, 2),
+                (3, 4, 1),
+                (10, 30, 17),
+                (40, dimension - 1, 3),
+                // slices wrapping around the end of the route
+                (dimension - 1, 3, 2),
+                (dimension - 2, 0, 1),
+                (30, 10, 25),
+            ];
+
+            for (i, j, segment_len) in moves {
+                let mut route = (0..dimension).collect::<Vec<_>>();
+                let route_len = tsp.get_route_len(&route).expect("has to be some");
+
+                let other_route_len =
+                    tsp.get_moved_segment_route_len(&route, route_len, i, j, segment_len);
+
+                route.rotate_left(i);
+                move_segment(&mut route[..=(j + dimension - i) % dimension], segment_len);
+
+                let moved_route_len = tsp.get_route_len(&route).expect("has to be some");
+
+                assert_eq!(moved_route_len, other_route_len, "{}", filename);
+            }
+        }
+    }
+
+    #[test]
+    fn reconnected_route_lens() {
+        for filename in ["test_files/berlin52.tsp", "test_files/ft70.atsp"] {
+            let tsp = TspParser::from_file(filename).expect("test file doesnt exist");
+            let dimension = tsp.dimension;
+
+            for reconnection in Reconnection::ALL {
+                for (i, j, k) in [(1, 2, 2), (3, 10, 20), (0, 5, 40), (7, 30, dimension - 1)] {
+                    let mut route = (0..dimension).collect::<Vec<_>>();
+                    let route_len = tsp.get_route_len(&route).expect("has to be some");
+
+                    let other_route_len =
+                        tsp.get_reconnected_route_len(&route, route_len, i, j, k, reconnection);
+
+                    reconnect(&mut route[i..=k], j - i, reconnection);
+
+                    let reconnected_route_len = tsp.get_route_len(&route).expect("has to be some");
+
+                    assert_eq!(
+                        reconnected_route_len, other_route_len,
+                        "{} {:?}",
+                        filename, reconnection
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn route_len_doesnt_overflow() {
+        let tsp: Tsp = Tsp::new(
+            Distances::Matrix(DenseMatrix::from_fn(3, |from, to| {
+                if from == to {
+                    0
+                } else {
+                    u32::MAX
+                }
+            })),
+            3,
+            TspType::Symmetric,
+        );
+
+        let route_len = tsp.get_route_len(&[0, 1, 2]).unwrap();
+
+        assert_eq!(3 * u32::MAX as u64, route_len);
+        assert_eq!(
+            route_len,
+            tsp.get_inverted_route_len(&[0, 1, 2], route_len, 0, 1)
+        );
+    }
+
+    #[test]
+    fn missing_fixed_edge() {
+        let tsp = TspParser::from_file("fixed_edges").expect("test file doesnt exist");
+
+        assert_eq!(&[(0, 3), (1, 4)], tsp.get_fixed_edges());
+        assert!(tsp.check_route_valid(&[0, 3, 2, 1, 4, 5]).is_ok());
+        assert!(tsp.check_route_valid(&[3, 0, 5, 4, 1, 2]).is_ok());
+        assert!(matches!(
+            tsp.check_route_valid(&[0, 1, 2, 3, 4, 5]),
+            Err(TspRouteError::MissingFixedEdge(0, 3))
+        ));
+    }
+
+    #[test]
+    fn moves_keep_fixed_edges() {
+        let tsp = TspParser::from_file("fixed_edges").expect("test file doesnt exist");
+        let route = [0, 3, 4, 1, 2, 5];
+
+        assert!(!tsp.is_inversion_allowed(&route, 1, 2));
+        assert!(tsp.is_inversion_allowed(&route, 2, 3));
+        assert!(!tsp.is_swap_allowed(&route, 1, 3));
+        assert!(tsp.is_swap_allowed(&route, 4, 5));
+
+        let tsp = Tsp::new(tsp.get_distances().clone(), 6, TspType::Asymmetric)
+            .with_fixed_edges(vec![(3, 4)])
+            .unwrap();
+
+        assert!(!tsp.is_inversion_allowed(&route, 0, 3));
+        assert!(tsp.is_inversion_allowed(&route, 3, 5));
+    }
+
+    #[test]
+    fn fixed_paths_work() {
+        let tsp = TspParser::from_file("fixed_edges").expect("test file doesnt exist");
+
+        assert_eq!(
+            vec![vec![0, 3], vec![1, 4], vec![2], vec![5]],
+            tsp.fixed_paths()
+        );
+
+        let tsp = Tsp::new(tsp.get_distances().clone(), 6, TspType::Asymmetric)
+            .with_fixed_edges(vec![(3, 2), (2, 0), (4, 5)])
+            .unwrap();
+
+        assert_eq!(vec![vec![1], vec![3, 2, 0], vec![4, 5]], tsp.fixed_paths());
+
+        // a fixed cycle through all vertices is a single path
+        let tsp = Tsp::new(tsp.get_distances().clone(), 6, TspType::Asymmetric)
+            .with_fixed_edges(vec![(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 0)])
+            .unwrap();
+
+        assert_eq!(vec![vec![0, 1, 2, 3, 4, 5]], tsp.fixed_paths());
+    }
+
+    #[test]
+    fn invalid_fixed_edges_are_rejected() {
+        let tsp = TspParser::from_file("fixed_edges").expect("test file doesnt exist");
+        let with_fixed_edges = |tsp_type, fixed_edges| {
+            Tsp::new(tsp.get_distances().clone(), 6, tsp_type).with_fixed_edges(fixed_edges)
+        };
+
+        assert_eq!(
+            Some(FixedEdgesError::VertexOutOfRange(6)),
+            with_fixed_edges(TspType::Symmetric, vec![(0, 6)]).err()
+        );
+        assert_eq!(
+            Some(FixedEdgesError::TooManyEdges(0)),
+            with_fixed_edges(TspType::Symmetric, vec![(0, 1), (0, 2), (3, 0)]).err()
+        );
+        assert_eq!(
+            Some(FixedEdgesError::TooManyEdges(0)),
+            with_fixed_edges(TspType::Asymmetric, vec![(0, 1), (0, 2)]).err()
+        );
+        assert_eq!(
+            Some(FixedEdgesError::ShortCycle(2)),
+            with_fixed_edges(TspType::Symmetric, vec![(0, 1), (1, 2), (2, 0)]).err()
+        );
+        assert_eq!(
+            Some(FixedEdgesError::ShortCycle(5)),
+            with_fixed_edges(TspType::Asymmetric, vec![(4, 5), (5, 4)]).err()
+        );
+        assert_eq!(
+            Some(FixedEdgesError::ShortCycle(3)),
+            with_fixed_edges(TspType::Asymmetric, vec![(3, 3)]).err()
+        );
+
+        // repeated edges are kept once
+        let tsp = with_fixed_edges(TspType::Symmetric, vec![(0, 1), (1, 0), (0, 1)]).unwrap();
+
+        assert_eq!(&[(0, 1)], tsp.get_fixed_edges());
+    }
 }
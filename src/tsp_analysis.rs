@@ -0,0 +1,191 @@
+use crate::distances::Distances;
+use crate::tsp::Tsp;
+use crate::weight::Weight;
+
+/// Properties of the edge weights of an instance, see `Tsp::analyze`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TspAnalysis<W = u32> {
+    /// Every edge weighs the same in both directions, the diagonal aside.
+    pub symmetric: bool,
+    /// Number of ordered triples of different nodes `(i, j, k)` where going
+    /// from `i` to `j` through `k` is shorter than the direct edge.
+    pub triangle_violations: usize,
+    /// Smallest and largest weight on the diagonal, like the 9999 sentinels
+    /// some FULL_MATRIX files put there.
+    pub diagonal_range: Option<(W, W)>,
+    /// Smallest and largest weight of edges between different nodes.
+    pub weight_range: Option<(W, W)>,
+    /// Pairs of nodes `(i, j)`, `i < j`, joined by a zero weight edge in at
+    /// least one direction.
+    pub zero_edges: Vec<(usize, usize)>,
+    /// Pairs of nodes from `zero_edges` which also have the same weights to
+    /// every other node, so one of them could be dropped.
+    pub duplicate_nodes: Vec<(usize, usize)>,
+}
+
+impl<W: Weight> TspAnalysis<W> {
+    /// Compares all triples of nodes, so it takes cubic time.
+    pub fn new(tsp: &Tsp<W>) -> TspAnalysis<W> {
+        let dimension = tsp.get_dimension();
+
+        let mut diagonal_range = None;
+        let mut weight_range = None;
+        let mut zero_edges = Vec::new();
+
+        for from in 0..dimension {
+            diagonal_range = Some(extend_range(diagonal_range, tsp.get_edge(from, from)));
+
+            for to in (0..dimension).filter(|&to| to != from) {
+                let weight = tsp.get_edge(from, to);
+
+                weight_range = Some(extend_range(weight_range, weight));
+
+                if weight == W::ZERO && (from < to || tsp.get_edge(to, from) != W::ZERO) {
+                    zero_edges.push((from.min(to), from.max(to)));
+                }
+            }
+        }
+
+        zero_edges.sort_unstable();
+
+        let duplicate_nodes = zero_edges
+            .iter()
+            .copied()
+            .filter(|&(first, second)| are_duplicates(tsp, first, second))
+            .collect();
+
+        TspAnalysis {
+            symmetric: is_symmetric(tsp.get_distances(), dimension),
+            triangle_violations: count_triangle_violations(tsp),
+            diagonal_range,
+            weight_range,
+            zero_edges,
+            duplicate_nodes,
+        }
+    }
+
+    /// Symmetric with zero diagonal and no shortcuts through other nodes,
+    /// zero weights between different nodes are allowed.
+    pub fn is_metric(&self) -> bool {
+        self.symmetric
+            && self.triangle_violations == 0
+            && self
+                .diagonal_range
+                .is_none_or(|(min, max)| min == W::ZERO && max == W::ZERO)
+    }
+}
+
+/// Only compares weights of edges between different nodes.
+pub(crate) fn is_symmetric<W: Weight>(distances: &Distances<W>, dimension: usize) -> bool {
+    match distances {
+        Distances::Matrix(_) => (0..dimension)
+            .all(|from| (0..from).all(|to| distances.get(from, to) == distances.get(to, from))),
+        _ => true,
+    }
+}
+
+fn count_triangle_violations<W: Weight>(tsp: &Tsp<W>) -> usize {
+    let dimension = tsp.get_dimension();
+    let edge_len = |from, to| W::Length::from(tsp.get_edge(from, to));
+
+    let mut violations = 0;
+
+    for through in 0..dimension {
+        for from in (0..dimension).filter(|&from| from != through) {
+            let first_len = edge_len(from, through);
+
+            for to in (0..dimension).filter(|&to| to != through && to != from) {
+                if first_len + edge_len(through, to) < edge_len(from, to) {
+                    violations += 1;
+                }
+            }
+        }
+    }
+
+    violations
+}
+
+fn are_duplicates<W: Weight>(tsp: &Tsp<W>, first: usize, second: usize) -> bool {
+    tsp.get_edge(first, second) == W::ZERO
+        && tsp.get_edge(second, first) == W::ZERO
+        && (0..tsp.get_dimension())
+            .filter(|&other| other != first && other != second)
+            .all(|other| {
+                tsp.get_edge(first, other) == tsp.get_edge(second, other)
+                    && tsp.get_edge(other, first) == tsp.get_edge(other, second)
+            })
+}
+
+fn extend_range<W: Weight>(range: Option<(W, W)>, weight: W) -> (W, W) {
+    match range {
+        Some((min, max)) if weight < min => (weight, max),
+        Some((min, max)) if weight > max => (min, weight),
+        Some(range) => range,
+        None => (weight, weight),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DenseMatrix, TspParser, TspType};
+
+    #[test]
+    fn full_matrix_analysis() {
+        let tsp = TspParser::from_file("full_matrix").expect("Couldn't parse file");
+
+        let analysis = tsp.analyze();
+
+        assert!(analysis.symmetric);
+        assert_eq!(0, analysis.triangle_violations);
+        assert_eq!(Some((9999, 9999)), analysis.diagonal_range);
+        assert_eq!(Some((2, 3)), analysis.weight_range);
+        assert!(analysis.zero_edges.is_empty());
+        assert!(!analysis.is_metric());
+    }
+
+    #[test]
+    fn asymmetric_analysis() {
+        let tsp = TspParser::from_file("test_files/br17.atsp").expect("Couldn't parse file");
+
+        let analysis = tsp.analyze();
+
+        assert!(!analysis.symmetric);
+        assert!(!analysis.is_metric());
+        assert_eq!(Some((9999, 9999)), analysis.diagonal_range);
+    }
+
+    #[test]
+    fn coordinates_are_metric() {
+        let tsp = TspParser::from_file("test_files/ulysses16.tsp").expect("Couldn't parse file");
+
+        let analysis = tsp.analyze();
+
+        assert!(analysis.is_metric());
+        assert_eq!(Some((0, 0)), analysis.diagonal_range);
+        assert!(analysis.duplicate_nodes.is_empty());
+    }
+
+    #[test]
+    fn triangle_violations_and_duplicates() {
+        let tsp: Tsp = Tsp::new(
+            Distances::Matrix(DenseMatrix::from_rows(vec![
+                vec![0, 0, 5, 1],
+                vec![0, 0, 5, 1],
+                vec![5, 5, 0, 1],
+                vec![1, 1, 1, 0],
+            ])),
+            4,
+            TspType::Symmetric,
+        );
+
+        let analysis = tsp.analyze();
+
+        assert!(analysis.symmetric);
+        assert_eq!(4, analysis.triangle_violations);
+        assert_eq!(Some((0, 5)), analysis.weight_range);
+        assert_eq!(vec![(0, 1)], analysis.zero_edges);
+        assert_eq!(vec![(0, 1)], analysis.duplicate_nodes);
+        assert!(!analysis.is_metric());
+    }
+}
@@ -0,0 +1,687 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use memmap2::Mmap;
+
+use crate::distances::{Distances, Metric};
+use crate::matrix::{DenseMatrix, PackedMatrix};
+use crate::tsp::{Tsp, TspType};
+use crate::tsp_parser::{ProblemType, TspHeader};
+use crate::weight::Weight;
+
+const MAGIC: &[u8; 8] = b"TSPCACHE";
+const VERSION: u32 = 2;
+
+// arrays start at multiples of 8 bytes, so a mapped file can be read in place
+const ALIGNMENT: usize = 8;
+
+// numbers temporary files of `TspCache::store` within the process
+static STORE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Binary copy of a parsed `Tsp`, see `TspParser::parse_file_cached`.
+///
+/// The file starts with a magic number, format version and CRC32 of the
+/// source, followed by the header, the distances and the optional sections,
+/// all little-endian. CRC32 of everything before it ends the file. A cache
+/// written by another version, for another source or weight type is stale.
+pub struct TspCache;
+
+impl TspCache {
+    /// `test_files/d1655.tsp` is cached in `d1655.tsp.cache` of the
+    /// `cache_directory`.
+    pub fn path(cache_directory: impl AsRef<Path>, filename: impl AsRef<Path>) -> PathBuf {
+        let mut name = filename
+            .as_ref()
+            .file_name()
+            .unwrap_or(filename.as_ref().as_os_str())
+            .to_owned();
+        name.push(".cache");
+
+        cache_directory.as_ref().join(name)
+    }
+
+    pub fn checksum(source: &[u8]) -> u32 {
+        crc32fast::hash(source)
+    }
+
+    /// Checksum of everything left in `reader`, read in chunks.
+    pub fn checksum_reader(mut reader: impl BufRead) -> io::Result<u32> {
+        let mut hasher = crc32fast::Hasher::new();
+
+        loop {
+            let chunk = reader.fill_buf()?;
+
+            if chunk.is_empty() {
+                return Ok(hasher.finalize());
+            }
+
+            hasher.update(chunk);
+
+            let len = chunk.len();
+            reader.consume(len);
+        }
+    }
+
+    /// `None` when the cache doesn't exist or is stale, broken files are
+    /// reported as `InvalidData`.
+    pub fn load<W: Weight>(
+        path: impl AsRef<Path>,
+        source_checksum: u32,
+    ) -> io::Result<Option<Tsp<W>>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error),
+        };
+
+        // SAFETY: the map is only read while the file is open, a cache
+        // rewritten meanwhile is replaced by rename and not modified in place
+        let bytes = unsafe { Mmap::map(&file)? };
+
+        TspCache::from_bytes(&bytes, source_checksum)
+    }
+
+    /// Writes into a temporary file first, so readers never see half of it.
+    /// Every call gets its own temporary file, concurrent writers only race
+    /// for the rename.
+    pub fn store<W: Weight>(
+        tsp: &Tsp<W>,
+        path: impl AsRef<Path>,
+        source_checksum: u32,
+    ) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            STORE_COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let mut file = File::create(&temporary)?;
+        file.write_all(&TspCache::to_bytes(tsp, source_checksum))?;
+        file.sync_all()?;
+
+        fs::rename(&temporary, path).inspect_err(|_| {
+            let _ = fs::remove_file(&temporary);
+        })
+    }
+
+    pub fn to_bytes<W: Weight>(tsp: &Tsp<W>, source_checksum: u32) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+
+        encoder.bytes.extend_from_slice(MAGIC);
+        encoder.u32(VERSION);
+        encoder.u32(source_checksum);
+        encoder.str(W::TAG);
+
+        encoder.header(tsp.get_header());
+        encoder.u64(tsp.get_dimension() as u64);
+        encoder.u8(match tsp.get_tsp_type() {
+            TspType::Symmetric => 0,
+            TspType::Asymmetric => 1,
+        });
+        encoder.distances(tsp.get_distances());
+
+        match tsp.get_distances() {
+            Distances::Coordinates(_, _) => encoder.u8(0),
+            _ => match tsp.coordinates() {
+                Some(display_data) => {
+                    encoder.u8(1);
+                    encoder.points(display_data);
+                }
+                None => encoder.u8(0),
+            },
+        }
+
+        encoder.u64(tsp.get_fixed_edges().len() as u64);
+
+        for &(from, to) in tsp.get_fixed_edges() {
+            encoder.u64(from as u64);
+            encoder.u64(to as u64);
+        }
+
+        let checksum = crc32fast::hash(&encoder.bytes);
+        encoder.u32(checksum);
+
+        encoder.bytes
+    }
+
+    pub fn from_bytes<W: Weight>(bytes: &[u8], source_checksum: u32) -> io::Result<Option<Tsp<W>>> {
+        let mut decoder = Decoder { bytes, offset: 0 };
+
+        if decoder.take(MAGIC.len())? != MAGIC {
+            return Err(invalid_data("not a tsp cache"));
+        }
+
+        let is_fresh = decoder.u32()? == VERSION
+            && decoder.u32()? == source_checksum
+            && decoder.str()? == W::TAG;
+
+        if !is_fresh {
+            return Ok(None);
+        }
+
+        let (body, checksum) = bytes.split_at(bytes.len().saturating_sub(4));
+
+        if crc32fast::hash(body).to_le_bytes() != checksum {
+            return Err(invalid_data("cache checksum doesn't match"));
+        }
+
+        let header = decoder.header()?;
+        let dimension = decoder.usize()?;
+        let tsp_type = match decoder.u8()? {
+            0 => TspType::Symmetric,
+            1 => TspType::Asymmetric,
+            _ => return Err(invalid_data("unknown tsp type")),
+        };
+        let distances = decoder.distances(dimension)?;
+
+        let mut tsp = Tsp::new(distances, dimension, tsp_type).with_header(header);
+
+        if decoder.u8()? == 1 {
+            tsp = tsp.with_display_data(decoder.points(dimension)?);
+        }
+
+        let fixed_edges = (0..decoder.usize()?)
+            .map(|_| Ok((decoder.vertex(dimension)?, decoder.vertex(dimension)?)))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        tsp.with_fixed_edges(fixed_edges)
+            .map(Some)
+            .map_err(|_| invalid_data("invalid fixed edges"))
+    }
+}
+
+#[derive(Default)]
+struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f64(&mut self, value: f64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn str(&mut self, value: &str) {
+        self.u64(value.len() as u64);
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    fn optional_str(&mut self, value: Option<&str>) {
+        match value {
+            Some(value) => {
+                self.u8(1);
+                self.str(value);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    fn align(&mut self) {
+        while !self.bytes.len().is_multiple_of(ALIGNMENT) {
+            self.bytes.push(0);
+        }
+    }
+
+    fn header(&mut self, header: &TspHeader) {
+        self.optional_str(header.name.as_deref());
+        self.optional_str(
+            header
+                .problem_type
+                .map(|problem_type| problem_type.keyword()),
+        );
+        self.optional_str(header.comment.as_deref());
+        self.u64(header.dimension as u64);
+        self.optional_str(header.edge_weight_type.as_deref());
+        self.optional_str(header.edge_weight_format.as_deref());
+        self.optional_str(header.edge_data_format.as_deref());
+        self.optional_str(header.display_data_type.as_deref());
+
+        match header.capacity {
+            Some(capacity) => {
+                self.u8(1);
+                self.u32(capacity);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    fn weights<W: Weight>(&mut self, weights: &[W]) {
+        self.align();
+
+        for &weight in weights {
+            weight.write_le(&mut self.bytes);
+        }
+    }
+
+    fn points(&mut self, points: &[(f64, f64)]) {
+        self.align();
+
+        for &(x, y) in points {
+            self.f64(x);
+            self.f64(y);
+        }
+    }
+
+    fn distances<W: Weight>(&mut self, distances: &Distances<W>) {
+        match distances {
+            Distances::Matrix(matrix) => {
+                self.u8(0);
+                self.weights(matrix.weights());
+            }
+            Distances::Packed(matrix) => {
+                self.u8(1);
+                self.weights(matrix.weights());
+            }
+            Distances::Coordinates(coords, metric) => {
+                self.u8(2);
+                self.u8(metric_code(*metric));
+                self.points(coords);
+            }
+            Distances::Coordinates3d(coords) => {
+                self.u8(3);
+                self.align();
+
+                for &(x, y, z) in coords {
+                    self.f64(x);
+                    self.f64(y);
+                    self.f64(z);
+                }
+            }
+            Distances::Graph(adjacency) => {
+                self.u8(4);
+
+                for neighbours in adjacency {
+                    self.u64(neighbours.len() as u64);
+
+                    for &neighbour in neighbours {
+                        self.u64(neighbour as u64);
+                    }
+                }
+            }
+        }
+    }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| invalid_data("cache is truncated"))?;
+
+        let bytes = &self.bytes[self.offset..end];
+        self.offset = end;
+
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn usize(&mut self) -> io::Result<usize> {
+        usize::try_from(self.u64()?).map_err(|_| invalid_data("number doesn't fit into usize"))
+    }
+
+    fn vertex(&mut self, dimension: usize) -> io::Result<usize> {
+        Some(self.usize()?)
+            .filter(|&vertex| vertex < dimension)
+            .ok_or_else(|| invalid_data("vertex out of range"))
+    }
+
+    fn str(&mut self) -> io::Result<&'a str> {
+        let len = self.usize()?;
+
+        std::str::from_utf8(self.take(len)?).map_err(|_| invalid_data("string isn't utf-8"))
+    }
+
+    fn optional_str(&mut self) -> io::Result<Option<String>> {
+        match self.u8()? {
+            0 => Ok(None),
+            _ => Ok(Some(self.str()?.to_string())),
+        }
+    }
+
+    fn align(&mut self) -> io::Result<()> {
+        let padding = (ALIGNMENT - self.offset % ALIGNMENT) % ALIGNMENT;
+
+        self.take(padding).map(|_| ())
+    }
+
+    // lengths are checked before allocating, a broken count can't ask for
+    // more memory than the file has
+    fn array(&mut self, len: usize, item_size: usize) -> io::Result<&'a [u8]> {
+        self.align()?;
+
+        let size = len
+            .checked_mul(item_size)
+            .ok_or_else(|| invalid_data("cache is truncated"))?;
+
+        self.take(size)
+    }
+
+    fn header(&mut self) -> io::Result<TspHeader> {
+        let name = self.optional_str()?;
+        let problem_type = match self.optional_str()? {
+            Some(keyword) => Some(
+                ProblemType::from_keyword(&keyword)
+                    .ok_or_else(|| invalid_data("unknown problem type"))?,
+            ),
+            None => None,
+        };
+
+        Ok(TspHeader {
+            name,
+            problem_type,
+            comment: self.optional_str()?,
+            dimension: self.usize()?,
+            edge_weight_type: self.optional_str()?,
+            edge_weight_format: self.optional_str()?,
+            edge_data_format: self.optional_str()?,
+            display_data_type: self.optional_str()?,
+            capacity: match self.u8()? {
+                0 => None,
+                _ => Some(self.u32()?),
+            },
+        })
+    }
+
+    fn weights<W: Weight>(&mut self, len: usize) -> io::Result<Vec<W>> {
+        Ok(self
+            .array(len, W::SIZE)?
+            .chunks_exact(W::SIZE)
+            .map(W::read_le)
+            .collect())
+    }
+
+    fn points(&mut self, len: usize) -> io::Result<Vec<(f64, f64)>> {
+        Ok(self
+            .array(len, 16)?
+            .chunks_exact(16)
+            .map(|point| (read_f64(&point[..8]), read_f64(&point[8..])))
+            .collect())
+    }
+
+    fn distances<W: Weight>(&mut self, dimension: usize) -> io::Result<Distances<W>> {
+        let square = dimension
+            .checked_mul(dimension)
+            .ok_or_else(|| invalid_data("dimension is too big"))?;
+
+        match self.u8()? {
+            0 => Ok(Distances::Matrix(DenseMatrix::from_weights(
+                dimension,
+                self.weights(square)?,
+            ))),
+            1 => Ok(Distances::Packed(PackedMatrix::from_weights(
+                dimension,
+                self.weights((square + dimension) / 2)?,
+            ))),
+            2 => {
+                let metric = metric_from_code(self.u8()?)?;
+
+                Ok(Distances::Coordinates(self.points(dimension)?, metric))
+            }
+            3 => Ok(Distances::Coordinates3d(
+                self.array(dimension, 24)?
+                    .chunks_exact(24)
+                    .map(|point| {
+                        (
+                            read_f64(&point[..8]),
+                            read_f64(&point[8..16]),
+                            read_f64(&point[16..]),
+                        )
+                    })
+                    .collect(),
+            )),
+            4 => {
+                let mut adjacency = Vec::new();
+
+                for _ in 0..dimension {
+                    let len = self.usize()?;
+                    let neighbours = (0..len)
+                        .map(|_| self.vertex(dimension))
+                        .collect::<io::Result<Vec<_>>>()?;
+
+                    adjacency.push(neighbours);
+                }
+
+                Ok(Distances::Graph(adjacency))
+            }
+            _ => Err(invalid_data("unknown distances")),
+        }
+    }
+}
+
+fn read_f64(bytes: &[u8]) -> f64 {
+    f64::from_le_bytes(bytes.try_into().expect("f64 has 8 bytes"))
+}
+
+fn metric_code(metric: Metric) -> u8 {
+    match metric {
+        Metric::Euc2d => 0,
+        Metric::Ceil2d => 1,
+        Metric::Man2d => 2,
+        Metric::Max2d => 3,
+        Metric::Geo => 4,
+        Metric::Att => 5,
+    }
+}
+
+fn metric_from_code(code: u8) -> io::Result<Metric> {
+    match code {
+        0 => Ok(Metric::Euc2d),
+        1 => Ok(Metric::Ceil2d),
+        2 => Ok(Metric::Man2d),
+        3 => Ok(Metric::Max2d),
+        4 => Ok(Metric::Geo),
+        5 => Ok(Metric::Att),
+        _ => Err(invalid_data("unknown metric")),
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Hcp, TspParser, TspParsingError};
+
+    fn check_same<W: Weight>(tsp: &Tsp<W>, cached: &Tsp<W>) {
+        assert_eq!(
+            tsp.get_distances().to_matrix(),
+            cached.get_distances().to_matrix()
+        );
+        assert_eq!(tsp.get_header(), cached.get_header());
+        assert_eq!(tsp.coordinates(), cached.coordinates());
+        assert_eq!(tsp.get_fixed_edges(), cached.get_fixed_edges());
+        assert_eq!(
+            format!("{:?}", tsp.get_tsp_type()),
+            format!("{:?}", cached.get_tsp_type())
+        );
+        assert_eq!(
+            std::mem::discriminant(tsp.get_distances()),
+            std::mem::discriminant(cached.get_distances())
+        );
+    }
+
+    fn check_round_trip<W: Weight>(tsp: &Tsp<W>) {
+        let bytes = TspCache::to_bytes(tsp, 7);
+
+        let cached = TspCache::from_bytes::<W>(&bytes, 7)
+            .expect("cache has to be valid")
+            .expect("cache has to be fresh");
+
+        check_same(tsp, &cached);
+    }
+
+    #[test]
+    fn round_trip_works() {
+        for filename in [
+            "euc_2d",
+            "euc_3d",
+            "full_matrix",
+            "lower_diag_row",
+            "fixed_edges",
+            "cvrp",
+            "sop",
+            "test_files/bays29.tsp",
+        ] {
+            check_round_trip(&TspParser::<u32>::parse_file(filename).unwrap());
+        }
+
+        check_round_trip(&Hcp::from_file("hcp_edge_list").unwrap().to_tsp());
+        check_round_trip(&TspParser::<f64>::parse_file("lower_diag_row").unwrap());
+    }
+
+    #[test]
+    fn stale_cache_is_ignored() {
+        let tsp = TspParser::<u32>::parse_file("euc_2d").unwrap();
+        let bytes = TspCache::to_bytes(&tsp, 7);
+
+        assert!(TspCache::from_bytes::<u32>(&bytes, 8).unwrap().is_none());
+        assert!(TspCache::from_bytes::<f64>(&bytes, 7).unwrap().is_none());
+    }
+
+    #[test]
+    fn broken_cache_is_invalid() {
+        let tsp = TspParser::<u32>::parse_file("full_matrix").unwrap();
+        let mut bytes = TspCache::to_bytes(&tsp, 7);
+
+        let truncated = TspCache::from_bytes::<u32>(&bytes[..bytes.len() - 10], 7);
+        assert_eq!(io::ErrorKind::InvalidData, truncated.unwrap_err().kind());
+
+        let last = bytes.len() - 5;
+        bytes[last] ^= 1;
+
+        let corrupted = TspCache::from_bytes::<u32>(&bytes, 7);
+        assert_eq!(io::ErrorKind::InvalidData, corrupted.unwrap_err().kind());
+
+        let not_cache = TspCache::from_bytes::<u32>(b"NAME: euc_2d", 7);
+        assert_eq!(io::ErrorKind::InvalidData, not_cache.unwrap_err().kind());
+    }
+
+    #[test]
+    fn streamed_checksum_matches() {
+        let source = fs::read("test_files/pr1002.tsp").unwrap();
+
+        // a small buffer makes the checksum span many chunks
+        let reader = std::io::BufReader::with_capacity(100, &source[..]);
+
+        assert_eq!(
+            TspCache::checksum(&source),
+            TspCache::checksum_reader(reader).unwrap()
+        );
+    }
+
+    #[test]
+    fn concurrent_stores_keep_cache_valid() {
+        let directory =
+            std::env::temp_dir().join(format!("tsp_cache_threads_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+
+        let tsp = TspParser::<u32>::parse_file("test_files/pr1002.tsp").unwrap();
+        let path = directory.join("pr1002.tsp.cache");
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| TspCache::store(&tsp, &path, 7).unwrap());
+            }
+        });
+
+        let cached = TspCache::load::<u32>(&path, 7)
+            .expect("cache has to be valid")
+            .expect("cache has to be fresh");
+
+        check_same(&tsp, &cached);
+
+        // no temporary file is left behind
+        assert_eq!(1, fs::read_dir(&directory).unwrap().count());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn parser_uses_cache() {
+        let directory = std::env::temp_dir().join(format!("tsp_cache_{}", std::process::id()));
+        let cache_directory = directory.join("cache");
+        fs::create_dir_all(&cache_directory).unwrap();
+
+        let filename = directory.join("euc_2d.tsp");
+        let filename = filename.to_str().unwrap();
+        let source = fs::read_to_string("euc_2d").unwrap();
+
+        fs::write(filename, &source).unwrap();
+
+        // plain parsing leaves no cache behind
+        TspParser::<u32>::parse_file(filename).expect("Couldn't parse file");
+        assert!(!TspCache::path(&directory, filename).exists());
+
+        let tsp = TspParser::<u32>::parse_file_cached(filename, &cache_directory)
+            .expect("Couldn't parse file");
+        let cached = TspCache::load::<u32>(
+            TspCache::path(&cache_directory, filename),
+            TspCache::checksum(source.as_bytes()),
+        )
+        .expect("cache has to be valid")
+        .expect("parser has to write the cache");
+
+        check_same(&tsp, &cached);
+        check_same(
+            &tsp,
+            &TspParser::parse_file_cached(filename, &cache_directory).unwrap(),
+        );
+
+        // a changed source makes the cache stale
+        let changed = source.replace("EUC_2D", "MAN_2D");
+        fs::write(filename, &changed).unwrap();
+
+        let tsp = TspParser::<u32>::parse_file_cached(filename, &cache_directory)
+            .expect("Couldn't parse file");
+
+        assert!(matches!(
+            tsp.get_distances(),
+            Distances::Coordinates(_, Metric::Man2d)
+        ));
+
+        // the cache has to be written somewhere
+        let missing = directory.join("missing");
+
+        assert!(matches!(
+            TspParser::<u32>::parse_file_cached(filename, missing),
+            Err(TspParsingError::CacheFailed(_))
+        ));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -1,14 +1,23 @@
 use crate::tsp::Tsp;
+use crate::weight::Weight;
 
+mod greedy;
 mod krandom;
+mod lin_kernighan;
 mod nearest_neighbour;
+mod or_opt;
+mod three_opt;
 mod two_opt;
 
+pub use greedy::Greedy;
 pub use krandom::KRandom;
-pub use nearest_neighbour::{NearestNeighbour, NearestNeighbourOptimized};
-pub use two_opt::TwoOpt;
-pub use two_opt::best_neighbourhood_invert;
+pub use lin_kernighan::{lin_kernighan, LinKernighan};
+pub use nearest_neighbour::{NearestNeighbour, NearestNeighbourOptimized, SopNearestNeighbour};
+pub use or_opt::{best_neighbourhood_move_segment, OrOpt};
+pub use three_opt::{three_opt, ThreeOpt};
+pub use two_opt::{SopTwoOpt, TwoOpt};
+pub use two_opt::{best_neighbourhood_invert, best_neighbourhood_invert_with_precedences};
 
-pub trait TspHeuristic {
-    fn get_route(&self, tsp: &Tsp) -> Vec<usize>;
+pub trait TspHeuristic<W: Weight = u32> {
+    fn get_route(&self, tsp: &Tsp<W>) -> Vec<usize>;
 }
@@ -0,0 +1,279 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::{KdTree, Tsp, TspHeuristic, TspType, Weight};
+
+/// Greedy matching, adds the lightest edges which keep every vertex on a
+/// path, until a single path visits all of them. Instances with coordinates
+/// find the lightest edges by a k-d tree, others sort all edges.
+#[derive(Default)]
+pub struct Greedy;
+
+impl Greedy {
+    pub fn new() -> Greedy {
+        Greedy
+    }
+}
+
+/// Paths made of the edges added so far.
+struct Fragments {
+    is_symmetric: bool,
+    // vertices joined to every vertex, the previous and the next one on
+    // the route for asymmetric instances
+    links: Vec<[Option<usize>; 2]>,
+    // the other end of the path for both ends of every path
+    other_end: Vec<usize>,
+    edges: usize,
+}
+
+impl Fragments {
+    fn new<W: Weight>(tsp: &Tsp<W>) -> Fragments {
+        let dimension = tsp.get_dimension();
+
+        let mut fragments = Fragments {
+            is_symmetric: matches!(tsp.get_tsp_type(), TspType::Symmetric),
+            links: vec![[None; 2]; dimension],
+            other_end: (0..dimension).collect(),
+            edges: 0,
+        };
+
+        for path in tsp.fixed_paths() {
+            for edge in path.windows(2) {
+                fragments.join(edge[0], edge[1]);
+            }
+        }
+
+        fragments
+    }
+
+    fn is_full(&self, vertex: usize) -> bool {
+        self.links[vertex].iter().all(Option::is_some)
+    }
+
+    fn can_join(&self, from: usize, to: usize) -> bool {
+        let has_room = if self.is_symmetric {
+            !self.is_full(from) && !self.is_full(to)
+        } else {
+            self.links[from][1].is_none() && self.links[to][0].is_none()
+        };
+
+        has_room && from != to && self.other_end[from] != to
+    }
+
+    fn join(&mut self, from: usize, to: usize) {
+        if self.is_symmetric {
+            for (vertex, other) in [(from, to), (to, from)] {
+                let free = self.links[vertex].iter().position(Option::is_none);
+
+                self.links[vertex][free.expect("vertex has to be an end")] = Some(other);
+            }
+        } else {
+            self.links[from][1] = Some(to);
+            self.links[to][0] = Some(from);
+        }
+
+        let (first, last) = (self.other_end[from], self.other_end[to]);
+
+        self.other_end[first] = last;
+        self.other_end[last] = first;
+        self.edges += 1;
+    }
+
+    fn into_route(self) -> Vec<usize> {
+        let dimension = self.links.len();
+
+        // asymmetric paths are walked from the vertex without a previous one
+        let Some(start) = (0..dimension).find(|&vertex| {
+            self.links[vertex][0].is_none()
+                || (self.is_symmetric && self.links[vertex][1].is_none())
+        }) else {
+            return Vec::new();
+        };
+
+        let mut route = Vec::with_capacity(dimension);
+        let mut previous = None;
+        let mut curr_vertex = Some(start);
+
+        while let Some(vertex) = curr_vertex {
+            route.push(vertex);
+
+            curr_vertex = self.links[vertex]
+                .into_iter()
+                .flatten()
+                .find(|&next| Some(next) != previous);
+            previous = Some(vertex);
+        }
+
+        route
+    }
+}
+
+// edge ordered by length, the shortest first
+#[derive(PartialEq)]
+struct Edge {
+    distance: f64,
+    from: usize,
+    to: usize,
+}
+
+impl Eq for Edge {}
+
+impl Ord for Edge {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance.total_cmp(&self.distance)
+    }
+}
+
+impl PartialOrd for Edge {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// every end of a path keeps its nearest possible partner in the heap, which
+// only gets farther as edges are added, so an edge still possible when it's
+// taken from the heap is the shortest one
+fn join_by_tree(fragments: &mut Fragments, mut tree: KdTree) {
+    let dimension = fragments.links.len();
+
+    let nearest = |fragments: &Fragments, tree: &KdTree, from: usize| {
+        let point = tree.get_point(from);
+
+        tree.nearest(point, |to| to != from && to != fragments.other_end[from])
+            .map(|to| Edge {
+                distance: (point.0 - tree.get_point(to).0).hypot(point.1 - tree.get_point(to).1),
+                from,
+                to,
+            })
+    };
+
+    let mut heap = BinaryHeap::new();
+
+    for vertex in 0..dimension {
+        if fragments.is_full(vertex) {
+            tree.delete(vertex);
+        }
+    }
+
+    for vertex in (0..dimension).filter(|&vertex| !fragments.is_full(vertex)) {
+        heap.extend(nearest(fragments, &tree, vertex));
+    }
+
+    while fragments.edges + 1 < dimension {
+        let Some(Edge { from, to, .. }) = heap.pop() else {
+            break;
+        };
+
+        if fragments.is_full(from) {
+            continue;
+        }
+
+        if fragments.can_join(from, to) {
+            fragments.join(from, to);
+
+            for vertex in [from, to] {
+                if fragments.is_full(vertex) {
+                    tree.delete(vertex);
+                }
+            }
+
+            if fragments.is_full(from) {
+                continue;
+            }
+        }
+
+        heap.extend(nearest(fragments, &tree, from));
+    }
+}
+
+fn join_sorted<W: Weight>(tsp: &Tsp<W>, fragments: &mut Fragments) {
+    let dimension = tsp.get_dimension();
+
+    let mut edges: Vec<_> = (0..dimension)
+        .flat_map(|from| (0..dimension).map(move |to| (from, to)))
+        .filter(|&(from, to)| from != to && (!fragments.is_symmetric || from < to))
+        .collect();
+
+    edges.sort_by(|&(first_from, first_to), &(second_from, second_to)| {
+        tsp.get_edge(first_from, first_to)
+            .partial_cmp(&tsp.get_edge(second_from, second_to))
+            .expect("weights are comparable")
+    });
+
+    for (from, to) in edges {
+        if fragments.edges + 1 >= dimension {
+            break;
+        }
+
+        if fragments.can_join(from, to) {
+            fragments.join(from, to);
+        }
+    }
+}
+
+impl<W: Weight> TspHeuristic<W> for Greedy {
+    fn get_route(&self, tsp: &Tsp<W>) -> Vec<usize> {
+        let mut fragments = Fragments::new(tsp);
+
+        match KdTree::from_tsp(tsp) {
+            Some(tree) => join_by_tree(&mut fragments, tree),
+            None => join_sorted(tsp, &mut fragments),
+        }
+
+        fragments.into_route()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn greedy_is_near_optimal() {
+        for (filename, optimal_route_len) in [
+            ("test_files/pcb442.tsp", 50778),
+            ("test_files/pr1002.tsp", 259045),
+        ] {
+            let tsp = TspParser::from_file(filename).expect("Couldn't parse file");
+
+            let route = Greedy::new().get_route(&tsp);
+            let route_len = tsp.get_route_len(&route).expect("Has to be valid route");
+
+            assert!(route_len * 100 <= optimal_route_len * 125, "{}", filename);
+        }
+    }
+
+    #[test]
+    fn tree_and_sorted_edges_agree() {
+        let tsp = TspParser::from_file("test_files/pr76.tsp").expect("Couldn't parse file");
+        let matrix = Tsp::new(
+            Distances::Matrix(DenseMatrix::from_rows(tsp.get_distances().to_matrix())),
+            tsp.get_dimension(),
+            TspType::Symmetric,
+        );
+
+        let route = Greedy::new().get_route(&tsp);
+        let matrix_route = Greedy::new().get_route(&matrix);
+
+        assert_eq!(
+            tsp.get_route_len(&route).unwrap(),
+            matrix.get_route_len(&matrix_route).unwrap()
+        );
+    }
+
+    #[test]
+    fn greedy_keeps_fixed_edges() {
+        for filename in ["test_files/berlin52.tsp", "test_files/ft70.atsp"] {
+            let tsp = TspParser::from_file(filename)
+                .expect("Couldn't parse file")
+                .with_fixed_edges(vec![(0, 41), (41, 10), (30, 20), (7, 8)])
+                .expect("fixed edges have to be valid");
+
+            let route = Greedy::new().get_route(&tsp);
+
+            tsp.check_route_valid(&route)
+                .expect("fixed edges have to be kept");
+        }
+    }
+}
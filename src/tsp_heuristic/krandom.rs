@@ -1,6 +1,6 @@
 use rand::prelude::*;
 
-use crate::{TspHeuristic, Tsp};
+use crate::{TspHeuristic, Tsp, TspType, Weight};
 
 pub struct KRandom {
     k: usize,
@@ -12,21 +12,30 @@ impl KRandom {
     }
 }
 
-impl TspHeuristic for KRandom {
-    fn get_route(&self, tsp: &Tsp) -> Vec<usize> {
-        let mut route = (0..tsp.get_dimension()).collect::<Vec<_>>();
+impl<W: Weight> TspHeuristic<W> for KRandom {
+    fn get_route(&self, tsp: &Tsp<W>) -> Vec<usize> {
+        let mut paths = tsp.fixed_paths();
         let mut best_route = None;
         let mut best_route_len = None;
         let mut rng = rand_pcg::Pcg64Mcg::new(thread_rng().gen());
 
         for _ in 0..self.k {
-            route.shuffle(&mut rng);
+            paths.shuffle(&mut rng);
+
+            if let TspType::Symmetric = tsp.get_tsp_type() {
+                for path in paths.iter_mut().filter(|path| path.len() > 1 && rng.gen()) {
+                    path.reverse();
+                }
+            }
+
+            // vertices glued by fixed edges stay next to each other
+            let route = paths.concat();
             let route_len = tsp.get_route_len(&route).expect("has to be valid route");
 
             if (best_route.is_none() && best_route_len.is_none())
                 || route_len < best_route_len.unwrap()
             {
-                best_route = Some(route.clone());
+                best_route = Some(route);
                 best_route_len = Some(route_len);
             }
         }
@@ -0,0 +1,508 @@
+use std::collections::VecDeque;
+
+use rand::prelude::*;
+
+use crate::tsp_heuristic::three_opt;
+use crate::{CandidateLists, SymmetricTransform, Tsp, TspHeuristic, TspType, Weight};
+
+/// Longest chain of 2-opt moves making up a single step.
+const MAX_DEPTH: usize = 50;
+
+/// Alternatives tried for the first steps of a chain, deeper steps take only
+/// the most promising one.
+const BREADTH: [usize; 2] = [5, 3];
+
+/// Longest segment moved by a kick.
+const MAX_KICK_LEN: usize = 50;
+
+/// Seed of the kicks, so the same route is always improved the same way.
+const KICK_SEED: u128 = 0x5eed;
+
+/// Lin–Kernighan, improves the route by variable depth chains of 2-opt
+/// moves which only have to pay off as a whole. New edges go to candidates
+/// of a city only and cities whose edges didn't change aren't searched again,
+/// then random kicks get the route out of the local optimum.
+///
+/// Asymmetric instances are solved through `SymmetricTransform`.
+pub struct LinKernighan<H> {
+    initial_heuristic: H,
+}
+
+impl<H> LinKernighan<H> {
+    pub fn new(initial_heuristic: H) -> Self {
+        LinKernighan { initial_heuristic }
+    }
+}
+
+/// Route kept as an array with positions of cities, which can be walked
+/// in both directions, so reversing a path reverses the shorter side.
+#[derive(Clone)]
+struct ArrayRoute {
+    route: Vec<usize>,
+    positions: Vec<usize>,
+    is_reversed: bool,
+}
+
+impl ArrayRoute {
+    fn new(route: Vec<usize>) -> ArrayRoute {
+        let mut positions = vec![0; route.len()];
+
+        for (i, &vertex) in route.iter().enumerate() {
+            positions[vertex] = i;
+        }
+
+        ArrayRoute {
+            route,
+            positions,
+            is_reversed: false,
+        }
+    }
+
+    fn next(&self, vertex: usize) -> usize {
+        let dimension = self.route.len();
+        let position = self.positions[vertex];
+
+        if self.is_reversed {
+            self.route[(position + dimension - 1) % dimension]
+        } else {
+            self.route[(position + 1) % dimension]
+        }
+    }
+
+    fn previous(&self, vertex: usize) -> usize {
+        let dimension = self.route.len();
+        let position = self.positions[vertex];
+
+        if self.is_reversed {
+            self.route[(position + 1) % dimension]
+        } else {
+            self.route[(position + dimension - 1) % dimension]
+        }
+    }
+
+    /// Walks the route the other way round.
+    fn turn(&mut self) {
+        self.is_reversed = !self.is_reversed;
+    }
+
+    /// Reverses the path walked from `from` to `to`.
+    fn reverse(&mut self, from: usize, to: usize) {
+        let dimension = self.route.len();
+
+        let (first, last) = if self.is_reversed {
+            (to, from)
+        } else {
+            (from, to)
+        };
+
+        let start = self.positions[first];
+        let len = (self.positions[last] + dimension - start) % dimension + 1;
+
+        // the rest of the route reversed is the same cycle walked backwards
+        if 2 * len > dimension {
+            self.reverse_positions((start + len) % dimension, dimension - len);
+            self.turn();
+        } else {
+            self.reverse_positions(start, len);
+        }
+    }
+
+    fn reverse_positions(&mut self, start: usize, len: usize) {
+        let dimension = self.route.len();
+
+        for i in 0..len / 2 {
+            let left = (start + i) % dimension;
+            let right = (start + len - 1 - i) % dimension;
+
+            self.route.swap(left, right);
+            self.positions[self.route[left]] = left;
+            self.positions[self.route[right]] = right;
+        }
+    }
+
+    /// Cities at `start` and after it in the array, `len` of them.
+    fn cities(&self, start: usize, len: usize) -> Vec<usize> {
+        let dimension = self.route.len();
+
+        (start..start + len)
+            .map(|position| self.route[position % dimension])
+            .collect()
+    }
+
+    /// Reorders three segments following `start` in the array from `1 2 3`
+    /// to `3 2 1`, the double bridge which chains of 2-opt moves can't undo.
+    fn double_bridge(&mut self, start: usize, lens: [usize; 3]) {
+        let dimension = self.route.len();
+        let [first_len, second_len, third_len] = lens;
+
+        let first = self.cities(start, first_len);
+        let second = self.cities(start + first_len, second_len);
+        let third = self.cities(start + first_len + second_len, third_len);
+
+        for (i, vertex) in third.into_iter().chain(second).chain(first).enumerate() {
+            let position = (start + i) % dimension;
+
+            self.route[position] = vertex;
+            self.positions[vertex] = position;
+        }
+    }
+
+    fn into_route(mut self) -> Vec<usize> {
+        if self.is_reversed {
+            self.route.reverse();
+        }
+
+        self.route
+    }
+}
+
+/// Lin–Kernighan on a symmetric instance, starting from `route`. Once no
+/// chain improves the route, it's kicked by random double bridges which are
+/// kept if the route gets shorter after improving it again.
+pub fn lin_kernighan<W: Weight>(tsp: &Tsp<W>, route: Vec<usize>) -> Vec<usize> {
+    let dimension = tsp.get_dimension();
+
+    let mut route_len = tsp.get_route_len(&route).expect("has to be valid route");
+
+    if dimension < 8 {
+        return route;
+    }
+
+    let mut search = Search {
+        tsp,
+        candidates: tsp.get_candidates(),
+        route: ArrayRoute::new(route),
+        added: Vec::new(),
+        removed: Vec::new(),
+        gain: W::Length::default(),
+    };
+
+    route_len -= search.optimize((0..dimension).collect());
+
+    let mut rng = rand_pcg::Pcg64Mcg::new(KICK_SEED);
+
+    for _ in 0..dimension {
+        let kicked_route = search.route.clone();
+
+        let Some((ends, kicked_len)) = search.kick(&mut rng, route_len) else {
+            continue;
+        };
+
+        let kicked_len = kicked_len - search.optimize(ends.into());
+
+        if kicked_len < route_len {
+            route_len = kicked_len;
+        } else {
+            search.route = kicked_route;
+        }
+    }
+
+    search.route.into_route()
+}
+
+struct Search<'a, W: Weight> {
+    tsp: &'a Tsp<W>,
+    candidates: &'a CandidateLists,
+    route: ArrayRoute,
+    // edges of the chain being built, `removed` starts with `(t1, t2)`
+    added: Vec<(usize, usize)>,
+    removed: Vec<(usize, usize)>,
+    // gain of the chain kept
+    gain: W::Length,
+}
+
+impl<W: Weight> Search<'_, W> {
+    fn edge_len(&self, from: usize, to: usize) -> W::Length {
+        self.tsp.get_edge(from, to).into()
+    }
+
+    /// Improves the route by chains starting from cities in the queue, until
+    /// none of them leads to one. Cities are queued again when edges next to
+    /// them change. Returns how much shorter the route got.
+    fn optimize(&mut self, mut queue: VecDeque<usize>) -> W::Length {
+        let mut is_queued = vec![false; self.tsp.get_dimension()];
+        let mut gain = W::Length::default();
+
+        for &vertex in &queue {
+            is_queued[vertex] = true;
+        }
+
+        while let Some(first) = queue.pop_front() {
+            is_queued[first] = false;
+
+            if !self.improve(first) {
+                continue;
+            }
+
+            gain += self.gain;
+
+            for &(from, to) in &self.removed {
+                for vertex in [from, to] {
+                    if !is_queued[vertex] {
+                        is_queued[vertex] = true;
+                        queue.push_back(vertex);
+                    }
+                }
+            }
+        }
+
+        gain
+    }
+
+    /// Tries chains starting by removing either edge of `t1`.
+    fn improve(&mut self, t1: usize) -> bool {
+        for _ in 0..2 {
+            let t2 = self.route.next(t1);
+
+            if !self.tsp.is_edge_fixed(t1, t2) {
+                self.added.clear();
+                self.removed.clear();
+                self.removed.push((t1, t2));
+
+                let gain = self.edge_len(t1, t2);
+
+                if self.step(t1, t2, gain, W::Length::default()) {
+                    return true;
+                }
+            }
+
+            self.route.turn();
+        }
+
+        false
+    }
+
+    /// Double bridge of three short segments at a random place, unless it
+    /// removes fixed edges. Returns cities at the ends of the changed edges
+    /// and the new length of the route.
+    fn kick(
+        &mut self,
+        rng: &mut impl Rng,
+        route_len: W::Length,
+    ) -> Option<(Vec<usize>, W::Length)> {
+        let dimension = self.tsp.get_dimension();
+        let max_len = MAX_KICK_LEN.min((dimension - 2) / 3);
+
+        let start = rng.gen_range(0..dimension);
+        let lens = [(); 3].map(|_| rng.gen_range(1..=max_len));
+
+        let [first_len, second_len, third_len] = lens;
+        let cities = self
+            .route
+            .cities(start, first_len + second_len + third_len + 2);
+        let last = cities.len() - 1;
+
+        // the segments follow the first city and end before the last one
+        let cuts = [0, first_len, first_len + second_len, last - 1];
+        let removed = cuts.map(|cut| (cities[cut], cities[cut + 1]));
+
+        if removed
+            .iter()
+            .any(|&(from, to)| self.tsp.is_edge_fixed(from, to))
+        {
+            return None;
+        }
+
+        let added = [
+            (cities[0], cities[cuts[2] + 1]),
+            (cities[last - 1], cities[cuts[1] + 1]),
+            (cities[cuts[2]], cities[1]),
+            (cities[cuts[1]], cities[last]),
+        ];
+
+        let edges_len = |edges: [(usize, usize); 4]| {
+            edges
+                .into_iter()
+                .fold(W::Length::default(), |len, (from, to)| {
+                    len + self.edge_len(from, to)
+                })
+        };
+
+        let kicked_len = route_len + edges_len(added) - edges_len(removed);
+
+        self.route.double_bridge((start + 1) % dimension, lens);
+
+        let ends = removed.iter().flat_map(|&(from, to)| [from, to]).collect();
+
+        Some((ends, kicked_len))
+    }
+
+    /// Extends the chain ending in `t2` by every promising step in turn,
+    /// while no shorter chain improves the route. Keeps the route changed and
+    /// returns true once the best closed chain gains more than `best_gain`.
+    ///
+    /// A step removes `(t1, t2)` and `(t4, t3)`, adds `(t2, t3)` and closes the
+    /// route with `(t1, t4)`, which the next step removes again.
+    fn step(&mut self, t1: usize, t2: usize, gain: W::Length, best_gain: W::Length) -> bool {
+        let depth = self.added.len();
+
+        for t3 in self.steps(t1, t2, gain) {
+            let t4 = self.route.previous(t3);
+            let gain = gain + self.edge_len(t4, t3) - self.edge_len(t2, t3);
+
+            self.route.reverse(t2, t4);
+            self.added.push((t2, t3));
+            self.removed.push((t4, t3));
+
+            let closing_len = self.edge_len(t4, t1);
+            let closed_gain = if closing_len < gain {
+                gain - closing_len
+            } else {
+                W::Length::default()
+            };
+
+            let is_better = closed_gain > best_gain;
+            let best_gain = if is_better { closed_gain } else { best_gain };
+
+            if depth + 1 < MAX_DEPTH && self.step(t1, t4, gain, best_gain) {
+                return true;
+            }
+
+            if is_better {
+                self.gain = closed_gain;
+                return true;
+            }
+
+            self.route.reverse(t4, t2);
+            self.added.pop();
+            self.removed.pop();
+
+            // alternatives are only searched while nothing improves
+            if best_gain > W::Length::default() || depth >= BREADTH.len() {
+                return false;
+            }
+        }
+
+        false
+    }
+
+    // next new edges (t2, t3) best by the lookahead gain `d(t4, t3) - d(t2, t3)`,
+    // the chain can't remove edges it added or add edges it removed
+    fn steps(&self, t1: usize, t2: usize, gain: W::Length) -> Vec<usize> {
+        let contains = |edges: &[(usize, usize)], from: usize, to: usize| {
+            edges.contains(&(from, to)) || edges.contains(&(to, from))
+        };
+
+        let mut steps = Vec::new();
+
+        for &t3 in self.candidates.get(t2) {
+            let added_len = self.edge_len(t2, t3);
+
+            if added_len >= gain {
+                break;
+            }
+
+            let t4 = self.route.previous(t3);
+
+            if t3 == t1
+                || t3 == self.route.next(t2)
+                || t4 == t1
+                || contains(&self.removed, t2, t3)
+                || contains(&self.added, t4, t3)
+                || self.tsp.is_edge_fixed(t4, t3)
+            {
+                continue;
+            }
+
+            steps.push((t3, self.edge_len(t4, t3) + gain - added_len));
+        }
+
+        steps.sort_by(|first, second| {
+            second
+                .1
+                .partial_cmp(&first.1)
+                .expect("lengths are comparable")
+        });
+
+        let breadth = BREADTH.get(self.added.len()).copied().unwrap_or(1);
+
+        steps.into_iter().take(breadth).map(|(t3, _)| t3).collect()
+    }
+}
+
+impl<W, H> TspHeuristic<W> for LinKernighan<H>
+where
+    W: Weight,
+    H: TspHeuristic<W>,
+{
+    fn get_route(&self, tsp: &Tsp<W>) -> Vec<usize> {
+        let initial_route = self.initial_heuristic.get_route(tsp);
+
+        if let TspType::Symmetric = tsp.get_tsp_type() {
+            return lin_kernighan(tsp, initial_route);
+        }
+
+        match SymmetricTransform::new(tsp) {
+            Some(transform) => {
+                let symmetric_route = transform.to_symmetric_route(&initial_route);
+                let route = lin_kernighan(transform.get_tsp(), symmetric_route);
+
+                transform.to_asymmetric_route(&route)
+            }
+            None => three_opt(tsp, initial_route),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn array_route_reverses_shorter_side() {
+        let mut route = ArrayRoute::new(vec![0, 1, 2, 3, 4, 5, 6, 7]);
+
+        route.reverse(2, 4);
+
+        assert_eq!(vec![0, 1, 4, 3, 2, 5, 6, 7], route.route);
+
+        route.reverse(5, 1);
+
+        assert!(route.is_reversed);
+        assert_eq!(3, route.next(4));
+        assert_eq!(vec![7, 6, 5, 4, 3, 2, 1, 0], route.into_route());
+    }
+
+    // the route has to be at most `percent` % of the optimal one
+    fn check_near_optimal(filename: &str, optimal_route_len: u64, percent: u64) {
+        let tsp = TspParser::from_file(filename).expect("Couldn't parse file");
+
+        // deterministic start and seeded kicks give the same route every run
+        let route = LinKernighan::new(NearestNeighbourOptimized::new()).get_route(&tsp);
+        let route_len = tsp.get_route_len(&route).expect("Has to be valid route");
+
+        assert!(
+            route_len * 100 <= optimal_route_len * percent,
+            "{} {}",
+            filename,
+            route_len
+        );
+    }
+
+    #[test]
+    fn lin_kernighan_is_near_optimal() {
+        check_near_optimal("test_files/berlin52.tsp", 7542, 102);
+        check_near_optimal("test_files/pr76.tsp", 108159, 102);
+        check_near_optimal("test_files/pcb442.tsp", 50778, 102);
+
+        // asymmetric instances get through the transform, which is harder
+        check_near_optimal("test_files/br17.atsp", 39, 110);
+        check_near_optimal("test_files/ft70.atsp", 38673, 110);
+    }
+
+    #[test]
+    fn lin_kernighan_keeps_fixed_edges() {
+        for filename in ["test_files/berlin52.tsp", "test_files/ft70.atsp"] {
+            let tsp = TspParser::from_file(filename)
+                .expect("Couldn't parse file")
+                .with_fixed_edges(vec![(0, 51), (51, 10), (20, 30), (7, 8)])
+                .expect("fixed edges have to be valid");
+
+            let route = LinKernighan::new(KRandom::new(10)).get_route(&tsp);
+
+            tsp.check_route_valid(&route)
+                .expect("fixed edges have to be kept");
+        }
+    }
+}
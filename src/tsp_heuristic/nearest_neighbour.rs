@@ -1,45 +1,156 @@
 use rand::prelude::*;
 
-use crate::{Tsp, TspHeuristic};
+use crate::{KdTree, Sop, SopHeuristic, Tsp, TspHeuristic, TspType, Weight};
 
-fn nearest_neighbour_inner(tsp: &Tsp, starting_vertex: usize) -> Vec<usize> {
+/// Nearest neighbour route from `starting_vertex`, the next path is found by
+/// the `tree` of all vertices if there is one.
+fn nearest_neighbour_inner<W: Weight>(
+    tsp: &Tsp<W>,
+    starting_vertex: usize,
+    mut tree: Option<KdTree>,
+) -> Vec<usize> {
     let dimension = tsp.get_dimension();
+    let is_symmetric = matches!(tsp.get_tsp_type(), TspType::Symmetric);
 
     assert!(starting_vertex < dimension, "Vertex bigger than dimension");
 
-    let mut route = vec![0; dimension];
-    let mut visited = vec![false; dimension];
+    // vertices glued by fixed edges are visited together, entered at either
+    // end of their path for symmetric instances
+    let mut paths = tsp.fixed_paths();
+    let mut visited = vec![false; paths.len()];
 
-    route[0] = starting_vertex;
-    visited[starting_vertex] = true;
+    let starting_path = paths
+        .iter()
+        .position(|path| path.contains(&starting_vertex))
+        .expect("every vertex lies on some path");
 
-    let mut curr_vertex = starting_vertex;
+    if is_symmetric && paths[starting_path].last() == Some(&starting_vertex) {
+        paths[starting_path].reverse();
+    }
+
+    let mut path_of = vec![0; dimension];
+
+    for (i, path) in paths.iter().enumerate() {
+        for &vertex in path {
+            path_of[vertex] = i;
+        }
+    }
+
+    // only ends of unvisited paths are left in the tree
+    if let Some(tree) = &mut tree {
+        for path in paths.iter().filter(|path| path.len() > 2) {
+            for &vertex in &path[1..path.len() - 1] {
+                tree.delete(vertex);
+            }
+        }
+    }
+
+    let mut route = Vec::with_capacity(dimension);
+    let mut next_path = Some(starting_path);
+
+    while let Some(path_index) = next_path {
+        visited[path_index] = true;
+        route.extend_from_slice(&paths[path_index]);
+
+        let path = &paths[path_index];
+        let curr_vertex = path[path.len() - 1];
+
+        let next = match &mut tree {
+            Some(tree) => {
+                tree.delete(path[0]);
+                tree.delete(curr_vertex);
+
+                tree.nearest(tree.get_point(curr_vertex), |_| true)
+                    .map(|vertex| (path_of[vertex], paths[path_of[vertex]][0] != vertex))
+            }
+            None => nearest_candidate_path(tsp, &paths, &path_of, &visited, curr_vertex)
+                .or_else(|| nearest_path(tsp, &paths, &visited, curr_vertex)),
+        };
+
+        if let Some((i, true)) = next {
+            paths[i].reverse();
+        }
+
+        next_path = next.map(|(i, _)| i);
+    }
 
-    for i in 1..dimension {
-        let mut min_len = u32::MAX;
-        let mut next_vertex = 0;
+    route
+}
+
+// nearest candidates are sorted by weight, so the first one at the end of
+// an unvisited path is taken, all paths are only scanned without one; other
+// lists may leave out nearer vertices and aren't even made
+fn nearest_candidate_path<W: Weight>(
+    tsp: &Tsp<W>,
+    paths: &[Vec<usize>],
+    path_of: &[usize],
+    visited: &[bool],
+    curr_vertex: usize,
+) -> Option<(usize, bool)> {
+    let is_symmetric = matches!(tsp.get_tsp_type(), TspType::Symmetric);
+
+    if !tsp.get_candidate_source().is_nearest(tsp) {
+        return None;
+    }
+
+    tsp.get_candidates()
+        .get(curr_vertex)
+        .iter()
+        .find_map(|&vertex| {
+            let i = path_of[vertex];
+            let path = &paths[i];
 
-        for i in 0..dimension {
             if visited[i] {
-                continue;
+                None
+            } else if path[0] == vertex {
+                Some((i, false))
+            } else if is_symmetric && path[path.len() - 1] == vertex {
+                Some((i, true))
+            } else {
+                None
             }
+        })
+}
+
+// unvisited path with the lightest edge from `curr_vertex` to its end and
+// whether it has to be reversed
+fn nearest_path<W: Weight>(
+    tsp: &Tsp<W>,
+    paths: &[Vec<usize>],
+    visited: &[bool],
+    curr_vertex: usize,
+) -> Option<(usize, bool)> {
+    let is_symmetric = matches!(tsp.get_tsp_type(), TspType::Symmetric);
 
-            let curr_len = tsp.get_edges()[curr_vertex][i];
+    let mut min_len = W::MAX;
+    let mut next_path = None;
+
+    for (i, path) in paths.iter().enumerate() {
+        if visited[i] {
+            continue;
+        }
+
+        let curr_len = tsp.get_edge(curr_vertex, path[0]);
+
+        if next_path.is_none() || curr_len < min_len {
+            min_len = curr_len;
+            next_path = Some((i, false));
+        }
+
+        if is_symmetric && path.len() > 1 {
+            let curr_len = tsp.get_edge(curr_vertex, path[path.len() - 1]);
 
             if curr_len < min_len {
                 min_len = curr_len;
-                next_vertex = i;
+                next_path = Some((i, true));
             }
         }
-
-        visited[next_vertex] = true;
-        route[i] = next_vertex;
-        curr_vertex = next_vertex;
     }
 
-    route
+    next_path
 }
 
+#[derive(Default)]
 pub struct NearestNeighbour;
 
 impl NearestNeighbour {
@@ -48,14 +159,15 @@ impl NearestNeighbour {
     }
 }
 
-impl TspHeuristic for NearestNeighbour {
-    fn get_route(&self, tsp: &Tsp) -> Vec<usize> {
+impl<W: Weight> TspHeuristic<W> for NearestNeighbour {
+    fn get_route(&self, tsp: &Tsp<W>) -> Vec<usize> {
         let first_vertex = thread_rng().gen_range(0..tsp.get_dimension());
 
-        nearest_neighbour_inner(tsp, first_vertex)
+        nearest_neighbour_inner(tsp, first_vertex, KdTree::from_tsp(tsp))
     }
 }
 
+#[derive(Default)]
 pub struct NearestNeighbourOptimized;
 
 impl NearestNeighbourOptimized {
@@ -64,13 +176,15 @@ impl NearestNeighbourOptimized {
     }
 }
 
-impl TspHeuristic for NearestNeighbourOptimized {
-    fn get_route(&self, tsp: &Tsp) -> Vec<usize> {
+impl<W: Weight> TspHeuristic<W> for NearestNeighbourOptimized {
+    fn get_route(&self, tsp: &Tsp<W>) -> Vec<usize> {
         let mut best_route = None;
         let mut best_route_len = None;
 
+        let tree = KdTree::from_tsp(tsp);
+
         for i in 0..tsp.get_dimension() {
-            let route = nearest_neighbour_inner(tsp, i);
+            let route = nearest_neighbour_inner(tsp, i, tree.clone());
             let route_len = tsp.get_route_len(&route).expect("has to be valid route");
 
             if (best_route.is_none() && best_route_len.is_none())
@@ -84,3 +198,105 @@ impl TspHeuristic for NearestNeighbourOptimized {
         best_route.expect("has to be valid route")
     }
 }
+
+/// Nearest neighbour which only moves to vertices whose predecessors were
+/// all visited, starting from the first vertex without predecessors.
+#[derive(Default)]
+pub struct SopNearestNeighbour;
+
+impl SopNearestNeighbour {
+    pub fn new() -> SopNearestNeighbour {
+        SopNearestNeighbour
+    }
+}
+
+impl<W: Weight> SopHeuristic<W> for SopNearestNeighbour {
+    fn get_route(&self, sop: &Sop<W>) -> Vec<usize> {
+        let tsp = sop.get_tsp();
+        let dimension = tsp.get_dimension();
+
+        let mut waiting_for = (0..dimension)
+            .map(|vertex| sop.get_predecessors(vertex).len())
+            .collect::<Vec<_>>();
+        let mut successors = vec![Vec::new(); dimension];
+
+        for &(before, after) in sop.get_precedences() {
+            successors[before].push(after);
+        }
+
+        let mut route = Vec::with_capacity(dimension);
+        let mut visited = vec![false; dimension];
+        let mut next_vertex = waiting_for.iter().position(|&count| count == 0);
+
+        while let Some(curr_vertex) = next_vertex {
+            visited[curr_vertex] = true;
+            route.push(curr_vertex);
+
+            for &successor in &successors[curr_vertex] {
+                waiting_for[successor] -= 1;
+            }
+
+            next_vertex = (0..dimension)
+                .filter(|&vertex| !visited[vertex] && waiting_for[vertex] == 0)
+                .min_by(|&a, &b| {
+                    tsp.get_edge(curr_vertex, a)
+                        .partial_cmp(&tsp.get_edge(curr_vertex, b))
+                        .expect("weights are comparable")
+                });
+        }
+
+        assert_eq!(dimension, route.len(), "Precedences can't be cyclic");
+
+        route
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CandidateSource, TspParser};
+
+    // lightest edge to any unvisited vertex, scanning all of them
+    fn brute_force_route_len(tsp: &Tsp<u32>, starting_vertex: usize) -> u64 {
+        let mut route = vec![starting_vertex];
+
+        while route.len() < tsp.get_dimension() {
+            let curr_vertex = route[route.len() - 1];
+            let next_vertex = (0..tsp.get_dimension())
+                .filter(|vertex| !route.contains(vertex))
+                .min_by_key(|&vertex| tsp.get_edge(curr_vertex, vertex))
+                .unwrap();
+
+            route.push(next_vertex);
+        }
+
+        tsp.get_route_len(&route).unwrap()
+    }
+
+    #[test]
+    fn nearest_neighbour_matches_brute_force() {
+        let tsp = TspParser::from_file("test_files/ulysses16.tsp").expect("Couldn't parse file");
+        let given = (0..16).map(|from| vec![(from + 1) % 16]).collect();
+
+        for source in [
+            CandidateSource::Quadrant(4),
+            CandidateSource::Nearest(3),
+            CandidateSource::Given(given),
+        ] {
+            let tsp = tsp.clone().with_candidates(source);
+
+            for vertex in 0..tsp.get_dimension() {
+                let route = nearest_neighbour_inner(&tsp, vertex, None);
+
+                assert_eq!(
+                    brute_force_route_len(&tsp, vertex),
+                    tsp.get_route_len(&route).unwrap()
+                );
+            }
+
+            let route = NearestNeighbourOptimized::new().get_route(&tsp);
+
+            assert_eq!(7943, tsp.get_route_len(&route).unwrap());
+        }
+    }
+}
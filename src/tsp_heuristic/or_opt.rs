@@ -0,0 +1,200 @@
+use crate::{neighbourhood, Tsp, TspHeuristic, Weight};
+
+/// Longest segment Or-opt tries to move.
+const MAX_SEGMENT_LEN: usize = 3;
+
+/// Or-opt, moves segments of up to three cities to the best other place in
+/// the route. Segments keep their direction, so asymmetric instances work
+/// just as well.
+pub struct OrOpt<H> {
+    initial_heuristic: H,
+}
+
+impl<H> OrOpt<H> {
+    pub fn new(initial_heuristic: H) -> Self {
+        OrOpt { initial_heuristic }
+    }
+}
+
+/// Best improving segment move while one exists. A segment starting at
+/// `route[i]` is moved behind `route[j]`, both may lie anywhere on the route,
+/// so segments and slices can wrap around its end. Only moves adding an edge
+/// from an end of the segment to its candidate are tried.
+pub fn best_neighbourhood_move_segment<W: Weight>(tsp: &Tsp<W>, route: Vec<usize>) -> Vec<usize> {
+    let dimension = tsp.get_dimension();
+    let candidates = tsp.get_candidates();
+
+    let mut best_route = route;
+    let mut best_route_len = tsp
+        .get_route_len(&best_route)
+        .expect("has to be valid route");
+
+    // the slice has to be longer than the segment and shorter than the route
+    let max_segment_len = MAX_SEGMENT_LEN.min(dimension.saturating_sub(2));
+
+    let mut positions = vec![0; dimension];
+
+    loop {
+        for (i, &vertex) in best_route.iter().enumerate() {
+            positions[vertex] = i;
+        }
+
+        let mut best_move = None;
+
+        for i in 0..dimension {
+            for segment_len in 1..=max_segment_len {
+                let first = best_route[i];
+                let last = best_route[(i + segment_len - 1) % dimension];
+
+                // the segment goes behind a candidate of its first city or in
+                // front of a candidate of its last one
+                let behind_first = candidates.get(first).iter().map(|&c| positions[c]);
+                let before_last = candidates
+                    .get(last)
+                    .iter()
+                    .map(|&c| (positions[c] + dimension - 1) % dimension);
+
+                for j in behind_first.chain(before_last) {
+                    let slice_len = (j + dimension - i) % dimension + 1;
+
+                    if slice_len <= segment_len
+                        || slice_len == dimension
+                        || !tsp.is_segment_move_allowed(&best_route, i, j, segment_len)
+                    {
+                        continue;
+                    }
+
+                    let route_len = tsp.get_moved_segment_route_len(
+                        &best_route,
+                        best_route_len,
+                        i,
+                        j,
+                        segment_len,
+                    );
+
+                    if route_len < best_move.map_or(best_route_len, |(_, _, _, len)| len) {
+                        best_move = Some((i, slice_len, segment_len, route_len));
+                    }
+                }
+            }
+        }
+
+        let Some((i, slice_len, segment_len, route_len)) = best_move else {
+            break;
+        };
+
+        // the route is a cycle, so it can start with the slice
+        best_route.rotate_left(i);
+        neighbourhood::move_segment(&mut best_route[..slice_len], segment_len);
+        best_route_len = route_len;
+    }
+
+    best_route
+}
+
+impl<W, H> TspHeuristic<W> for OrOpt<H>
+where
+    W: Weight,
+    H: TspHeuristic<W>,
+{
+    fn get_route(&self, tsp: &Tsp<W>) -> Vec<usize> {
+        let initial_route = self.initial_heuristic.get_route(tsp);
+        best_neighbourhood_move_segment(tsp, initial_route)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    struct FixedRoute(Vec<usize>);
+
+    impl TspHeuristic for FixedRoute {
+        fn get_route(&self, _: &Tsp) -> Vec<usize> {
+            self.0.clone()
+        }
+    }
+
+    fn check_improves(filename: &str) {
+        let tsp = TspParser::from_file(filename).expect("Couldn't parse file");
+
+        // the deterministic start isn't Or-opt optimal on either instance
+        let initial_route = NearestNeighbourOptimized::new().get_route(&tsp);
+        let route = OrOpt::new(FixedRoute(initial_route.clone())).get_route(&tsp);
+
+        let initial_route_len = tsp.get_route_len(&initial_route).unwrap();
+        let route_len = tsp.get_route_len(&route).expect("Has to be valid route");
+
+        assert!(route_len < initial_route_len, "{}", filename);
+
+        // candidates of every city make up the whole neighbourhood
+        let dimension = tsp.get_dimension();
+        let tsp = tsp.with_candidates(CandidateSource::Nearest(dimension - 1));
+        let route = best_neighbourhood_move_segment(&tsp, initial_route);
+
+        check_no_segment_move_improves(&tsp, &route);
+    }
+
+    // every segment of up to three cities put behind every other city,
+    // built from scratch and wrapping around the end of the route
+    fn check_no_segment_move_improves(tsp: &Tsp, route: &[usize]) {
+        let dimension = tsp.get_dimension();
+        let route_len = tsp.get_route_len(route).unwrap();
+
+        for start in 0..dimension {
+            for segment_len in 1..=MAX_SEGMENT_LEN {
+                let mut rest = route.to_vec();
+                rest.rotate_left(start);
+
+                let segment: Vec<_> = rest.drain(..segment_len).collect();
+
+                for position in 0..rest.len() - 1 {
+                    let mut moved = rest.clone();
+                    moved.splice(position + 1..position + 1, segment.iter().copied());
+
+                    if let Ok(moved_len) = tsp.get_route_len(&moved) {
+                        assert!(moved_len >= route_len, "{:?} improves", moved);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn segments_wrap_around() {
+        let tsp = TspParser::from_file("test_files/berlin52.tsp").expect("Couldn't parse file");
+
+        let mut route = OrOpt::new(NearestNeighbourOptimized::new()).get_route(&tsp);
+        let route_len = tsp.get_route_len(&route).unwrap();
+
+        // two neighbouring cities misplaced together, one at the end of the
+        // route and the other at its start
+        let segment: Vec<_> = route.drain(10..12).collect();
+        route.splice(30..30, segment);
+        route.rotate_left(31);
+
+        let route = best_neighbourhood_move_segment(&tsp, route);
+
+        assert_eq!(route_len, tsp.get_route_len(&route).unwrap());
+    }
+
+    #[test]
+    fn or_opt_improves_route() {
+        check_improves("test_files/berlin52.tsp");
+        check_improves("test_files/ft70.atsp");
+    }
+
+    #[test]
+    fn or_opt_keeps_fixed_edges() {
+        let tsp = TspParser::from_file("test_files/ft70.atsp")
+            .expect("Couldn't parse file")
+            .with_fixed_edges(vec![(0, 69), (69, 10), (30, 20), (7, 8)])
+            .expect("fixed edges have to be valid");
+
+        let route = OrOpt::new(KRandom::new(10)).get_route(&tsp);
+
+        tsp.check_route_valid(&route)
+            .expect("fixed edges have to be kept");
+    }
+}
@@ -0,0 +1,367 @@
+use std::collections::VecDeque;
+
+use crate::neighbourhood::{self, Reconnection};
+use crate::{Tsp, TspHeuristic, TspType, Weight};
+
+/// 3-opt, removes up to three edges of the route and joins the pieces back
+/// in the cheapest of the seven other ways. Asymmetric instances only get
+/// the segment exchange, every other case reverses a segment.
+///
+/// Edges to remove are chained by the gain criterion, a new edge has to be
+/// shorter than the removed ones before it and go to a candidate, so only
+/// a tiny part of the cubic neighbourhood is evaluated.
+pub struct ThreeOpt<H> {
+    initial_heuristic: H,
+}
+
+impl<H> ThreeOpt<H> {
+    pub fn new(initial_heuristic: H) -> Self {
+        ThreeOpt { initial_heuristic }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Move {
+    Invert(usize, usize),
+    Reconnect(usize, usize, usize, Reconnection),
+}
+
+/// Applies improving moves found around cities from a queue, until no city
+/// leads to one. Cities are queued again when edges next to them change.
+pub fn three_opt<W: Weight>(tsp: &Tsp<W>, route: Vec<usize>) -> Vec<usize> {
+    let dimension = tsp.get_dimension();
+
+    let mut route = route;
+    let mut route_len = tsp.get_route_len(&route).expect("has to be valid route");
+
+    if dimension < 5 {
+        return route;
+    }
+
+    let mut positions = vec![0; dimension];
+
+    for (i, &vertex) in route.iter().enumerate() {
+        positions[vertex] = i;
+    }
+
+    let mut queue: VecDeque<usize> = route.iter().copied().collect();
+    let mut is_queued = vec![true; dimension];
+
+    while let Some(first) = queue.pop_front() {
+        is_queued[first] = false;
+
+        let Some((best_move, best_route_len)) =
+            find_move(tsp, &route, &positions, route_len, first)
+        else {
+            continue;
+        };
+
+        let (i, k, touched) = match best_move {
+            Move::Invert(i, j) => (i, j, vec![i - 1, i, j, j + 1]),
+            Move::Reconnect(i, j, k, _) => (i, k, vec![i - 1, i, j - 1, j, k, k + 1]),
+        };
+
+        // cities at the ends of changed edges
+        let touched: Vec<_> = touched
+            .into_iter()
+            .map(|position| route[position % dimension])
+            .collect();
+
+        match best_move {
+            Move::Invert(i, j) => neighbourhood::invert(&mut route[i..=j]),
+            Move::Reconnect(i, j, k, reconnection) => {
+                neighbourhood::reconnect(&mut route[i..=k], j - i, reconnection)
+            }
+        }
+
+        for (position, &vertex) in route.iter().enumerate().take(k + 1).skip(i) {
+            positions[vertex] = position;
+        }
+
+        route_len = best_route_len;
+
+        for vertex in touched {
+            if !is_queued[vertex] {
+                is_queued[vertex] = true;
+                queue.push_back(vertex);
+            }
+        }
+    }
+
+    route
+}
+
+/// First improving move removing an edge next to `t1`. Cities are named as
+/// usual, removed edges are `(t1, t2)`, `(t3, t4)`, `(t5, t6)` and new ones
+/// `(t2, t3)`, `(t4, t5)`, `(t6, t1)`.
+fn find_move<W: Weight>(
+    tsp: &Tsp<W>,
+    route: &[usize],
+    positions: &[usize],
+    route_len: W::Length,
+    t1: usize,
+) -> Option<(Move, W::Length)> {
+    let dimension = tsp.get_dimension();
+    let is_symmetric = matches!(tsp.get_tsp_type(), TspType::Symmetric);
+    let candidates = tsp.get_candidates();
+
+    let next = |vertex: usize| route[(positions[vertex] + 1) % dimension];
+    let previous = |vertex: usize| route[(positions[vertex] + dimension - 1) % dimension];
+
+    // only prunes the search, so asymmetric edges take the cheaper direction
+    let weight = |from: usize, to: usize| -> W::Length {
+        let weight = tsp.get_edge(from, to);
+
+        if is_symmetric {
+            return weight.into();
+        }
+
+        let reverse_weight = tsp.get_edge(to, from);
+
+        if reverse_weight < weight {
+            reverse_weight.into()
+        } else {
+            weight.into()
+        }
+    };
+
+    let is_improving = |candidate: Option<(Move, W::Length)>| match candidate {
+        Some((_, len)) if len < route_len => candidate,
+        _ => None,
+    };
+
+    for t2 in [next(t1), previous(t1)] {
+        let removed_len = weight(t1, t2);
+
+        for &t3 in candidates.get(t2).iter().filter(|&&t3| t3 != t1) {
+            let added_len = weight(t2, t3);
+
+            if added_len >= removed_len {
+                continue;
+            }
+
+            for t4 in [next(t3), previous(t3)] {
+                if t4 == t1 || t4 == t2 {
+                    continue;
+                }
+
+                if is_symmetric {
+                    let candidate = invert_move(tsp, route, positions, route_len, [t1, t2, t3, t4]);
+
+                    if let Some(candidate) = is_improving(candidate) {
+                        return Some(candidate);
+                    }
+                }
+
+                let removed_len = removed_len + weight(t3, t4);
+
+                for &t5 in candidates
+                    .get(t4)
+                    .iter()
+                    .filter(|t5| ![t1, t2, t3].contains(t5))
+                {
+                    if added_len + weight(t4, t5) >= removed_len {
+                        continue;
+                    }
+
+                    for t6 in [next(t5), previous(t5)] {
+                        if [t1, t2, t3, t4].contains(&t6) {
+                            continue;
+                        }
+
+                        let candidate = reconnect_move(
+                            tsp,
+                            route,
+                            positions,
+                            route_len,
+                            [t1, t2, t3, t4, t5, t6],
+                        );
+
+                        if let Some(candidate) = is_improving(candidate) {
+                            return Some(candidate);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// position of the edge between `from` and `to` in the route, counted by
+// its earlier city
+fn edge_position(positions: &[usize], from: usize, to: usize) -> usize {
+    let dimension = positions.len();
+
+    if (positions[from] + 1) % dimension == positions[to] {
+        positions[from]
+    } else {
+        positions[to]
+    }
+}
+
+// replacing (t1, t2) and (t3, t4) with (t2, t3) and (t4, t1) inverts the
+// route between them, if the edges are walked in opposite directions
+fn invert_move<W: Weight>(
+    tsp: &Tsp<W>,
+    route: &[usize],
+    positions: &[usize],
+    route_len: W::Length,
+    [t1, t2, t3, t4]: [usize; 4],
+) -> Option<(Move, W::Length)> {
+    let is_first_forward = positions[t2] == (positions[t1] + 1) % positions.len();
+    let is_second_forward = positions[t4] == (positions[t3] + 1) % positions.len();
+
+    if is_first_forward == is_second_forward {
+        return None;
+    }
+
+    let mut cuts = [
+        edge_position(positions, t1, t2),
+        edge_position(positions, t3, t4),
+    ];
+    cuts.sort_unstable();
+
+    let (i, j) = (cuts[0] + 1, cuts[1]);
+
+    if !tsp.is_inversion_allowed(route, i, j) {
+        return None;
+    }
+
+    let route_len = tsp.get_inverted_route_len(route, route_len, i, j);
+
+    Some((Move::Invert(i, j), route_len))
+}
+
+// finds which of the reconnections adds exactly the new edges, other sets
+// of new edges split the route into several cycles
+fn reconnect_move<W: Weight>(
+    tsp: &Tsp<W>,
+    route: &[usize],
+    positions: &[usize],
+    route_len: W::Length,
+    [t1, t2, t3, t4, t5, t6]: [usize; 6],
+) -> Option<(Move, W::Length)> {
+    let dimension = tsp.get_dimension();
+
+    let mut cuts = [
+        edge_position(positions, t1, t2),
+        edge_position(positions, t3, t4),
+        edge_position(positions, t5, t6),
+    ];
+    cuts.sort_unstable();
+
+    if cuts[0] == cuts[1] || cuts[1] == cuts[2] {
+        return None;
+    }
+
+    let (i, j, k) = (cuts[0] + 1, cuts[1] + 1, cuts[2]);
+
+    let ends = [
+        route[i - 1],
+        route[i],
+        route[j - 1],
+        route[j],
+        route[k],
+        route[(k + 1) % dimension],
+    ];
+
+    let undirected = |edges: [(usize, usize); 3]| {
+        let mut edges = edges.map(|(from, to)| (from.min(to), from.max(to)));
+        edges.sort_unstable();
+        edges
+    };
+
+    let new_edges = undirected([(t2, t3), (t4, t5), (t6, t1)]);
+
+    let reconnection = Reconnection::ALL
+        .into_iter()
+        .find(|reconnection| undirected(reconnection.added_edges(ends)) == new_edges)?;
+
+    let is_reversal_allowed =
+        reconnection == Reconnection::Exchange || matches!(tsp.get_tsp_type(), TspType::Symmetric);
+
+    if !is_reversal_allowed || !tsp.is_reconnection_allowed(route, i, j, k, reconnection) {
+        return None;
+    }
+
+    let route_len = tsp.get_reconnected_route_len(route, route_len, i, j, k, reconnection);
+
+    Some((Move::Reconnect(i, j, k, reconnection), route_len))
+}
+
+impl<W, H> TspHeuristic<W> for ThreeOpt<H>
+where
+    W: Weight,
+    H: TspHeuristic<W>,
+{
+    fn get_route(&self, tsp: &Tsp<W>) -> Vec<usize> {
+        let initial_route = self.initial_heuristic.get_route(tsp);
+        three_opt(tsp, initial_route)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    fn check_improves(filename: &str) -> u64 {
+        let tsp = TspParser::from_file(filename).expect("Couldn't parse file");
+
+        // the same deterministic route is improved every run
+        let initial_route = NearestNeighbourOptimized::new().get_route(&tsp);
+        let route = three_opt(&tsp, initial_route.clone());
+
+        let initial_route_len = tsp.get_route_len(&initial_route).unwrap();
+        let route_len = tsp.get_route_len(&route).expect("Has to be valid route");
+
+        assert!(route_len < initial_route_len, "{}", filename);
+
+        route_len
+    }
+
+    #[test]
+    fn three_opt_improves_route() {
+        // optimal route is 7542 long
+        assert!(check_improves("test_files/berlin52.tsp") < 7542 * 21 / 20);
+
+        check_improves("test_files/ft70.atsp");
+    }
+
+    #[test]
+    fn three_opt_is_two_opt_optimal() {
+        let tsp = TspParser::from_file("test_files/pr76.tsp").expect("Couldn't parse file");
+        let dimension = tsp.get_dimension();
+
+        // candidates of every city make up the whole neighbourhood
+        let tsp = tsp.with_candidates(CandidateSource::Nearest(dimension - 1));
+        let route = ThreeOpt::new(NearestNeighbourOptimized::new()).get_route(&tsp);
+        let route_len = tsp.get_route_len(&route).expect("Has to be valid route");
+
+        // optimal route is 108159 long
+        assert!(route_len * 100 <= 108159 * 105, "{}", route_len);
+
+        for i in 0..dimension {
+            for j in i + 1..dimension {
+                assert!(tsp.get_inverted_route_len(&route, route_len, i, j) >= route_len);
+            }
+        }
+    }
+
+    #[test]
+    fn three_opt_keeps_fixed_edges() {
+        for filename in ["test_files/berlin52.tsp", "test_files/ft70.atsp"] {
+            let tsp = TspParser::from_file(filename)
+                .expect("Couldn't parse file")
+                .with_fixed_edges(vec![(0, 41), (41, 10), (30, 20), (7, 8)])
+                .expect("fixed edges have to be valid");
+
+            let route = ThreeOpt::new(KRandom::new(10)).get_route(&tsp);
+
+            tsp.check_route_valid(&route)
+                .expect("fixed edges have to be kept");
+        }
+    }
+}
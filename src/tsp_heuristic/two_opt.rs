@@ -1,116 +1,157 @@
-use crate::{neighbourhood, Tsp, TspHeuristic};
+use crate::{neighbourhood, Sop, SopHeuristic, Tsp, TspHeuristic, Weight};
 
-pub struct TwoOpt<H>
-where
-    H: TspHeuristic,
-{
+pub struct TwoOpt<H> {
     initial_heuristic: H,
 }
 
-impl<H> TwoOpt<H>
-where
-    H: TspHeuristic,
-{
+impl<H> TwoOpt<H> {
     pub fn new(initial_heuristic: H) -> Self {
         TwoOpt { initial_heuristic }
     }
 }
 
-fn best_neighbourhood_generic<F>(tsp: &Tsp, route: Vec<usize>, neighbourhood: F) -> Vec<usize>
-where
-    F: Fn(&mut [usize]),
-{
+/// Best improving inversion while one exists. Inverting `route[i..=j]` adds
+/// edges `(route[i - 1], route[j])` and `(route[i], route[j + 1])`, only
+/// inversions adding an edge from a city to its candidate are tried.
+pub fn best_neighbourhood_invert<W: Weight>(tsp: &Tsp<W>, route: Vec<usize>) -> Vec<usize> {
     let dimension = tsp.get_dimension();
+    let candidates = tsp.get_candidates();
 
     let mut best_route = route;
     let mut best_route_len = tsp
         .get_route_len(&best_route)
         .expect("has to be valid route");
 
-    let mut indexes = None;
-    let mut curr_best_route_len = None;
+    let mut positions = vec![0; dimension];
 
     loop {
-        for i in 0..dimension {
-            for j in i..dimension {
-                neighbourhood(&mut best_route[i..=j]);
-                let route_len = tsp.get_route_len(&best_route).expect("has to be some");
-                neighbourhood(&mut best_route[i..=j]);
-
-                if (curr_best_route_len.is_none() && route_len < best_route_len)
-                    || (curr_best_route_len.is_some() && route_len < curr_best_route_len.unwrap())
-                {
-                    indexes = Some((i, j));
-                    curr_best_route_len = Some(route_len);
+        for (i, &vertex) in best_route.iter().enumerate() {
+            positions[vertex] = i;
+        }
+
+        let mut best_move = None;
+
+        for vertex in 0..dimension {
+            for &candidate in candidates.get(vertex) {
+                let first = positions[vertex].min(positions[candidate]);
+                let second = positions[vertex].max(positions[candidate]);
+
+                for (i, j) in [(first + 1, second), (first, second - 1)] {
+                    if i >= j || !tsp.is_inversion_allowed(&best_route, i, j) {
+                        continue;
+                    }
+
+                    let route_len = tsp.get_inverted_route_len(&best_route, best_route_len, i, j);
+
+                    if route_len < best_move.map_or(best_route_len, |(_, _, len)| len) {
+                        best_move = Some((i, j, route_len));
+                    }
                 }
             }
         }
 
-        if curr_best_route_len.is_none() && indexes.is_none() {
+        let Some((i, j, route_len)) = best_move else {
             break;
-        }
+        };
 
-        let (i, j) = indexes.unwrap();
+        neighbourhood::invert(&mut best_route[i..=j]);
+        best_route_len = route_len;
+    }
 
-        neighbourhood(&mut best_route[i..=j]);
-        best_route_len = curr_best_route_len.unwrap();
+    best_route
+}
 
-        indexes = None;
-        curr_best_route_len = None;
+impl<W, H> TspHeuristic<W> for TwoOpt<H>
+where
+    W: Weight,
+    H: TspHeuristic<W>,
+{
+    fn get_route(&self, tsp: &Tsp<W>) -> Vec<usize> {
+        let initial_route = self.initial_heuristic.get_route(tsp);
+        best_neighbourhood_invert(tsp, initial_route)
     }
+}
 
-    best_route
+/// 2-opt for the sequential ordering problem, only inverts segments without
+/// precedences between their own vertices, so every route stays feasible.
+pub struct SopTwoOpt<H> {
+    initial_heuristic: H,
 }
 
-pub fn best_neighbourhood_invert(tsp: &Tsp, route: Vec<usize>) -> Vec<usize> {
+impl<H> SopTwoOpt<H> {
+    pub fn new(initial_heuristic: H) -> Self {
+        SopTwoOpt { initial_heuristic }
+    }
+}
+
+pub fn best_neighbourhood_invert_with_precedences<W: Weight>(
+    sop: &Sop<W>,
+    route: Vec<usize>,
+) -> Vec<usize> {
+    let tsp = sop.get_tsp();
     let dimension = tsp.get_dimension();
 
     let mut best_route = route;
-    let mut best_route_len = tsp
+    let mut best_route_len = sop
         .get_route_len(&best_route)
-        .expect("has to be valid route");
+        .expect("has to be feasible route");
 
-    let mut indexes = None;
-    let mut curr_best_route_len = None;
+    let mut positions = vec![0; dimension];
 
     loop {
+        for (i, &vertex) in best_route.iter().enumerate() {
+            positions[vertex] = i;
+        }
+
+        let mut best_move = None;
+
         for i in 0..dimension {
+            // latest predecessor of the segment, it has to stay in front of it
+            let mut last_predecessor = None;
+
             for j in i..dimension {
+                last_predecessor = sop
+                    .get_predecessors(best_route[j])
+                    .iter()
+                    .map(|&predecessor| positions[predecessor])
+                    .chain(last_predecessor)
+                    .max();
+
+                if last_predecessor.is_some_and(|position| position >= i) {
+                    break;
+                }
+
+                if !tsp.is_inversion_allowed(&best_route, i, j) {
+                    continue;
+                }
+
                 let route_len = tsp.get_inverted_route_len(&best_route, best_route_len, i, j);
 
-                if (curr_best_route_len.is_none() && route_len < best_route_len)
-                    || (curr_best_route_len.is_some() && route_len < curr_best_route_len.unwrap())
-                {
-                    indexes = Some((i, j));
-                    curr_best_route_len = Some(route_len);
+                if route_len < best_move.map_or(best_route_len, |(_, _, len)| len) {
+                    best_move = Some((i, j, route_len));
                 }
             }
         }
 
-        if curr_best_route_len.is_none() && indexes.is_none() {
+        let Some((i, j, route_len)) = best_move else {
             break;
-        }
-
-        let (i, j) = indexes.unwrap();
+        };
 
         neighbourhood::invert(&mut best_route[i..=j]);
-        best_route_len = curr_best_route_len.unwrap();
-
-        indexes = None;
-        curr_best_route_len = None;
+        best_route_len = route_len;
     }
 
     best_route
 }
 
-impl<H> TspHeuristic for TwoOpt<H>
+impl<W, H> SopHeuristic<W> for SopTwoOpt<H>
 where
-    H: TspHeuristic,
+    W: Weight,
+    H: SopHeuristic<W>,
 {
-    fn get_route(&self, tsp: &Tsp) -> Vec<usize> {
-        let initial_route = self.initial_heuristic.get_route(tsp);
-        best_neighbourhood_invert(tsp, initial_route)
-        // best_neighbourhood_generic(tsp, initial_route, neighbourhood::invert)
+    fn get_route(&self, sop: &Sop<W>) -> Vec<usize> {
+        let initial_route = self.initial_heuristic.get_route(sop);
+        best_neighbourhood_invert_with_precedences(sop, initial_route)
     }
 }
 
@@ -142,4 +183,139 @@ mod tests {
 
         assert_eq!(fast_route_len, slow_route_len);
     }
+
+    // every neighbour evaluated from scratch, the brute-force oracle
+    fn best_neighbourhood_generic<W, F>(
+        tsp: &Tsp<W>,
+        route: Vec<usize>,
+        neighbourhood: F,
+    ) -> Vec<usize>
+    where
+        W: Weight,
+        F: Fn(&mut [usize]),
+    {
+        let dimension = tsp.get_dimension();
+
+        let mut best_route = route;
+        let mut best_route_len = tsp
+            .get_route_len(&best_route)
+            .expect("has to be valid route");
+
+        let mut indexes = None;
+        let mut curr_best_route_len = None;
+
+        loop {
+            for i in 0..dimension {
+                for j in i..dimension {
+                    neighbourhood(&mut best_route[i..=j]);
+                    let route_len = tsp.get_route_len(&best_route);
+                    neighbourhood(&mut best_route[i..=j]);
+
+                    // neighbours breaking a fixed edge aren't valid routes
+                    let Ok(route_len) = route_len else {
+                        continue;
+                    };
+
+                    if (curr_best_route_len.is_none() && route_len < best_route_len)
+                        || (curr_best_route_len.is_some()
+                            && route_len < curr_best_route_len.unwrap())
+                    {
+                        indexes = Some((i, j));
+                        curr_best_route_len = Some(route_len);
+                    }
+                }
+            }
+
+            if curr_best_route_len.is_none() && indexes.is_none() {
+                break;
+            }
+
+            let (i, j) = indexes.unwrap();
+
+            neighbourhood(&mut best_route[i..=j]);
+            best_route_len = curr_best_route_len.unwrap();
+
+            indexes = None;
+            curr_best_route_len = None;
+        }
+
+        best_route
+    }
+
+    #[test]
+    fn candidate_moves_match_full_neighbourhood() {
+        for filename in ["test_files/berlin52.tsp", "test_files/pr76.tsp"] {
+            let tsp = TspParser::from_file(filename).expect("Couldn't parse file");
+            let dimension = tsp.get_dimension();
+
+            // candidates of every city make up the whole neighbourhood
+            let tsp = tsp.with_candidates(CandidateSource::Nearest(dimension - 1));
+            let route = TwoOpt::new(NearestNeighbourOptimized::new()).get_route(&tsp);
+
+            assert_eq!(
+                route,
+                best_neighbourhood_generic(&tsp, route.clone(), neighbourhood::invert),
+                "{}",
+                filename
+            );
+        }
+    }
+
+    fn check_fixed_edges_kept<H: TspHeuristic>(tsp: &Tsp, heuristic: H) {
+        let route = heuristic.get_route(tsp);
+
+        tsp.check_route_valid(&route)
+            .expect("fixed edges have to be kept");
+    }
+
+    #[test]
+    fn fixed_edges_are_kept() {
+        let tsp = TspParser::from_file("fixed_edges").expect("Couldn't parse file");
+
+        check_fixed_edges_kept(&tsp, NearestNeighbour::new());
+        check_fixed_edges_kept(&tsp, TwoOpt::new(NearestNeighbourOptimized::new()));
+
+        let tsp = TspParser::from_file("test_files/berlin52.tsp")
+            .expect("Couldn't parse file")
+            .with_fixed_edges(vec![(0, 51), (51, 10), (20, 30), (7, 8)])
+            .expect("fixed edges have to be valid");
+
+        check_fixed_edges_kept(&tsp, TwoOpt::new(NearestNeighbour::new()));
+        check_fixed_edges_kept(&tsp, TwoOpt::new(KRandom::new(10)));
+
+        let tsp = TspParser::from_file("test_files/ft70.atsp")
+            .expect("Couldn't parse file")
+            .with_fixed_edges(vec![(0, 69), (69, 10), (30, 20), (7, 8)])
+            .expect("fixed edges have to be valid");
+
+        check_fixed_edges_kept(&tsp, TwoOpt::new(NearestNeighbour::new()));
+        check_fixed_edges_kept(&tsp, TwoOpt::new(KRandom::new(10)));
+    }
+
+    struct FixedRoute(Vec<usize>);
+
+    impl SopHeuristic for FixedRoute {
+        fn get_route(&self, _: &Sop) -> Vec<usize> {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn sop_two_opt_keeps_precedences() {
+        let sop = Sop::from_file("sop").expect("Couldn't parse file");
+        let initial_route = vec![0, 4, 2, 3, 1, 5, 6];
+
+        let route = SopTwoOpt::new(FixedRoute(initial_route.clone())).get_route(&sop);
+
+        let initial_route_len = sop.get_route_len(&initial_route).unwrap();
+        let route_len = sop.get_route_len(&route).expect("Has to be feasible route");
+
+        assert_eq!(82, initial_route_len);
+        assert_eq!(46, route_len);
+
+        let route = SopTwoOpt::new(SopNearestNeighbour::new()).get_route(&sop);
+
+        sop.check_route_feasible(&route)
+            .expect("Has to be feasible route");
+    }
 }
@@ -1,118 +1,271 @@
-use std::str::Lines;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::marker::PhantomData;
+use std::path::Path;
+use std::str::FromStr;
 
+use crate::distances::Distances;
 use crate::tsp::{Tsp, TspType};
+use crate::tsp_analysis::is_symmetric;
+use crate::tsp_cache::TspCache;
+use crate::weight::Weight;
 
-mod euc2d;
+mod compression;
+mod demand;
+pub(crate) mod edge_data;
+mod error;
 mod full_matrix;
+mod header;
 mod lower_diag_row;
+mod node_coord;
+mod sop_matrix;
+pub(crate) mod tokens;
+pub(crate) mod triangular;
 
 #[cfg(test)]
 mod tests;
 
-use euc2d::Euc2dTspParser;
+use compression::decompress;
+pub use compression::Compression;
+use demand::{parse_demands, parse_depots};
+pub use error::{TokenContext, TspParsingError};
+use edge_data::parse_edge_list;
 use full_matrix::FullMatrixTspParser;
+pub use header::{ProblemType, TspHeader};
 use lower_diag_row::LowerDiagRowTspParser;
-
+use node_coord::{
+    parse_positions, AttTspParser, Ceil2dTspParser, Euc2dTspParser, Euc3dTspParser, GeoTspParser,
+    Man2dTspParser, Max2dTspParser,
+};
+use sop_matrix::parse_sop_matrix;
+use tokens::Tokens;
+use triangular::{TriangularFormat, TriangularTspParser};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum TspFileType {
     LowerDiagRow,
+    UpperRow,
+    LowerRow,
+    UpperDiagRow,
+    UpperCol,
+    LowerCol,
+    UpperDiagCol,
+    LowerDiagCol,
     FullMatrix,
     Euc2d,
+    Euc3d,
+    Ceil2d,
+    Man2d,
+    Max2d,
+    Geo,
+    Att,
 }
 
-#[derive(Debug)]
-pub enum TspParsingError {
-    FileDoesntExist,
-    NoExplicitFileType,
-    NoFileType,
-    NoDimension,
-    NotEnoughData,
-    DimensionNotANumber,
-    WeightNotANumber,
+pub trait VariantParser {
+    fn parse<R: BufRead, W: Weight>(
+        tokens: &mut Tokens<R>,
+        dimension: usize,
+    ) -> Result<Distances<W>, TspParsingError>;
 }
 
-pub trait VariantParser {
-    fn parse(file_lines: &mut Lines, dimension: usize) -> Result<Vec<Vec<u32>>, TspParsingError>;
+/// Sections which don't fit into `Tsp`, used by other problem types.
+#[derive(Debug, Default)]
+pub(crate) struct ExtraSections {
+    pub demands: Option<Vec<u32>>,
+    pub depots: Vec<usize>,
+    pub precedences: Vec<(usize, usize)>,
 }
 
-pub struct TspParser;
+/// Reads TSPLIB files into `Tsp<W>`, weights of explicit matrices are parsed
+/// straight into `W`, e.g. `TspParser::<f64>::parse_file` keeps fractional
+/// costs. `from_*` functions are shorthands for the default `u32` weights.
+pub struct TspParser<W = u32> {
+    weight: PhantomData<W>,
+}
 
 impl TspParser {
     pub fn from_file(filename: &str) -> Result<Tsp, TspParsingError> {
-        let file_content =
-            std::fs::read_to_string(filename).map_err(|_| TspParsingError::FileDoesntExist)?;
-        let mut file_lines = file_content.lines();
+        TspParser::parse_file(filename)
+    }
 
-        let dimension = TspParser::check_dimension(&mut file_lines)?;
-        let file_type = TspParser::check_file_type(&mut file_lines)?;
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(content: &str) -> Result<Tsp, TspParsingError> {
+        TspParser::parse_str(content)
+    }
 
-        loop {
-            let line = file_lines.next().ok_or(TspParsingError::NotEnoughData)?;
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Tsp, TspParsingError> {
+        TspParser::parse_reader(reader)
+    }
+}
 
-            if line.contains("EDGE_WEIGHT_SECTION") || line.contains("NODE_COORD_SECTION") {
-                break;
-            }
-        }
+impl<W: Weight> TspParser<W> {
+    /// Gzip and bzip2 files are decompressed on the fly with the `gzip` and
+    /// `bzip2` features.
+    pub fn parse_file(filename: &str) -> Result<Tsp<W>, TspParsingError> {
+        let file = File::open(filename).map_err(TspParsingError::FileDoesntExist)?;
 
-        let edges = match file_type {
-            TspFileType::LowerDiagRow => LowerDiagRowTspParser::parse(&mut file_lines, dimension),
-            TspFileType::FullMatrix => FullMatrixTspParser::parse(&mut file_lines, dimension),
-            TspFileType::Euc2d => Euc2dTspParser::parse(&mut file_lines, dimension),
-        }?;
+        TspParser::parse_reader(BufReader::new(file))
+    }
 
-        match file_type {
-            TspFileType::FullMatrix => Ok(Tsp::new(edges, dimension, TspType::Asymmetric)),
-            _ => Ok(Tsp::new(edges, dimension, TspType::Symmetric)),
+    /// Reuses `TspCache` of the file in `cache_directory` when its checksum
+    /// matches, otherwise parses the file and refreshes the cache.
+    pub fn parse_file_cached(
+        filename: &str,
+        cache_directory: impl AsRef<Path>,
+    ) -> Result<Tsp<W>, TspParsingError> {
+        let file = File::open(filename).map_err(TspParsingError::FileDoesntExist)?;
+        let checksum =
+            TspCache::checksum_reader(BufReader::new(file)).map_err(TspParsingError::ReadFailed)?;
+        let cache_path = TspCache::path(cache_directory, filename);
+
+        // a broken cache is simply written again
+        if let Ok(Some(tsp)) = TspCache::load(&cache_path, checksum) {
+            return Ok(tsp);
         }
+
+        // the source is read again, it may not fit into memory
+        let tsp = TspParser::parse_file(filename)?;
+
+        TspCache::store(&tsp, &cache_path, checksum).map_err(TspParsingError::CacheFailed)?;
+
+        Ok(tsp)
     }
 
-    fn check_dimension(file_lines: &mut Lines) -> Result<usize, TspParsingError> {
-        let dimension = loop {
-            let line = file_lines.next().ok_or(TspParsingError::NoDimension)?;
+    pub fn parse_str(content: &str) -> Result<Tsp<W>, TspParsingError> {
+        TspParser::parse_reader(content.as_bytes())
+    }
 
-            if line.contains("DIMENSION") {
-                break line;
-            }
+    pub fn parse_reader<R: BufRead>(reader: R) -> Result<Tsp<W>, TspParsingError> {
+        TspParser::parse_with_extra_sections(reader).map(|(tsp, _)| tsp)
+    }
+
+    pub(crate) fn parse_with_extra_sections<R: BufRead>(
+        reader: R,
+    ) -> Result<(Tsp<W>, ExtraSections), TspParsingError> {
+        let mut tokens = Tokens::new(decompress(reader)?);
+
+        let (header, section) = TspHeader::parse(&mut tokens)?;
+        let dimension = header.dimension;
+        let file_type = header.file_type()?;
+
+        let data_section = match file_type.edge_weight_format() {
+            Some(_) => "EDGE_WEIGHT_SECTION",
+            None => "NODE_COORD_SECTION",
         };
 
-        dimension
-            .split_whitespace()
-            .last()
-            .ok_or(TspParsingError::NoDimension)?
-            .parse::<usize>()
-            .map_err(|_| TspParsingError::DimensionNotANumber)
-    }
+        let is_sop_matrix = header.problem_type == Some(ProblemType::Sop)
+            && file_type == TspFileType::FullMatrix;
+
+        let mut section = Some(section);
+        let mut distances = None;
+        let mut display_data = None;
+        let mut fixed_edges = Vec::new();
+        let mut extra_sections = ExtraSections::default();
 
-    fn check_file_type(file_lines: &mut Lines) -> Result<TspFileType, TspParsingError> {
-        let edge_weight_type = loop {
-            let line = file_lines.next().ok_or(TspParsingError::NoFileType)?;
+        while let Some(current) = section {
+            tokens.set_section(&current);
 
-            if line.contains("EDGE_WEIGHT_TYPE") {
-                break line;
+            match current.as_str() {
+                "EOF" => break,
+                current if current == data_section && is_sop_matrix => {
+                    let (sop_distances, precedences) = parse_sop_matrix(&mut tokens, dimension)?;
+
+                    distances = Some(sop_distances);
+                    extra_sections.precedences = precedences;
+                }
+                current if current == data_section => {
+                    distances = Some(TspParser::parse_distances(
+                        &mut tokens,
+                        file_type,
+                        dimension,
+                    )?)
+                }
+                "NODE_COORD_SECTION" | "DISPLAY_DATA_SECTION" => {
+                    display_data = Some(parse_positions(&mut tokens, dimension)?)
+                }
+                "FIXED_EDGES_SECTION" => {
+                    fixed_edges.extend(parse_edge_list(&mut tokens, dimension)?)
+                }
+                "DEMAND_SECTION" => {
+                    extra_sections.demands = Some(parse_demands(&mut tokens, dimension)?)
+                }
+                "DEPOT_SECTION" => extra_sections.depots = parse_depots(&mut tokens, dimension)?,
+                _ => {}
             }
-        };
 
-        if edge_weight_type.contains("EUC_2D") {
-            Ok(TspFileType::Euc2d)
-        } else if edge_weight_type.contains("EXPLICIT") {
-            TspParser::check_explicit_file_type(file_lines)
-        } else {
-            Err(TspParsingError::NoFileType)
+            section = tokens.next_section()?;
         }
-    }
 
-    fn check_explicit_file_type(file_lines: &mut Lines) -> Result<TspFileType, TspParsingError> {
-        let edge_weight_format = file_lines
-            .next()
-            .ok_or(TspParsingError::NoExplicitFileType)?;
+        let distances = distances.ok_or_else(|| TspParsingError::NotEnoughData {
+            section: data_section.to_string(),
+            line: tokens.line_number(),
+        })?;
 
-        if edge_weight_format.contains("FULL_MATRIX") {
-            Ok(TspFileType::FullMatrix)
-        } else if edge_weight_format.contains("LOWER_DIAG_ROW") {
-            Ok(TspFileType::LowerDiagRow)
+        // FULL_MATRIX files are often symmetric, which lets heuristics use
+        // the cheaper symmetric moves, SOP precedences are always directed
+        let tsp_type = if is_sop_matrix || !is_symmetric(&distances, dimension) {
+            TspType::Asymmetric
         } else {
-            Err(TspParsingError::NoExplicitFileType)
+            TspType::Symmetric
+        };
+
+        let tsp = Tsp::new(distances, dimension, tsp_type)
+            .with_header(header)
+            .with_fixed_edges(fixed_edges)
+            .map_err(TspParsingError::InvalidFixedEdges)?;
+
+        let tsp = match display_data {
+            Some(display_data) => tsp.with_display_data(display_data),
+            None => tsp,
+        };
+
+        Ok((tsp, extra_sections))
+    }
+
+    fn parse_distances<R: BufRead>(
+        tokens: &mut Tokens<R>,
+        file_type: TspFileType,
+        dimension: usize,
+    ) -> Result<Distances<W>, TspParsingError> {
+        match file_type {
+            TspFileType::LowerDiagRow => LowerDiagRowTspParser::parse(tokens, dimension),
+            TspFileType::UpperRow => {
+                TriangularTspParser::parse(tokens, dimension, TriangularFormat::UpperRow)
+            }
+            TspFileType::LowerRow => {
+                TriangularTspParser::parse(tokens, dimension, TriangularFormat::LowerRow)
+            }
+            TspFileType::UpperDiagRow => {
+                TriangularTspParser::parse(tokens, dimension, TriangularFormat::UpperDiagRow)
+            }
+            TspFileType::UpperCol => {
+                TriangularTspParser::parse(tokens, dimension, TriangularFormat::UpperCol)
+            }
+            TspFileType::LowerCol => {
+                TriangularTspParser::parse(tokens, dimension, TriangularFormat::LowerCol)
+            }
+            TspFileType::UpperDiagCol => {
+                TriangularTspParser::parse(tokens, dimension, TriangularFormat::UpperDiagCol)
+            }
+            TspFileType::LowerDiagCol => {
+                TriangularTspParser::parse(tokens, dimension, TriangularFormat::LowerDiagCol)
+            }
+            TspFileType::FullMatrix => FullMatrixTspParser::parse(tokens, dimension),
+            TspFileType::Euc2d => Euc2dTspParser::parse(tokens, dimension),
+            TspFileType::Euc3d => Euc3dTspParser::parse(tokens, dimension),
+            TspFileType::Ceil2d => Ceil2dTspParser::parse(tokens, dimension),
+            TspFileType::Man2d => Man2dTspParser::parse(tokens, dimension),
+            TspFileType::Max2d => Max2dTspParser::parse(tokens, dimension),
+            TspFileType::Geo => GeoTspParser::parse(tokens, dimension),
+            TspFileType::Att => AttTspParser::parse(tokens, dimension),
         }
     }
 }
+
+impl<W: Weight> FromStr for Tsp<W> {
+    type Err = TspParsingError;
+
+    fn from_str(content: &str) -> Result<Tsp<W>, TspParsingError> {
+        TspParser::parse_str(content)
+    }
+}
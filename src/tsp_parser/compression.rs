@@ -0,0 +1,88 @@
+use std::fmt;
+use std::io::BufRead;
+#[cfg(any(feature = "gzip", feature = "bzip2"))]
+use std::io::BufReader;
+
+use crate::tsp_parser::TspParsingError;
+
+/// Compressed inputs recognized by their magic bytes.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Compression {
+    Gzip,
+    Bzip2,
+}
+
+impl Compression {
+    fn detect(start: &[u8]) -> Option<Compression> {
+        if start.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if start.starts_with(b"BZh") {
+            Some(Compression::Bzip2)
+        } else {
+            None
+        }
+    }
+
+    /// Cargo feature the decoder lives behind.
+    pub fn feature(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Bzip2 => "bzip2",
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.feature())
+    }
+}
+
+/// Plain input is passed through, compressed input gets a streaming decoder,
+/// so the whole file is never decompressed into memory.
+pub(crate) fn decompress<'a, R: BufRead + 'a>(
+    mut reader: R,
+) -> Result<Box<dyn BufRead + 'a>, TspParsingError> {
+    let start = reader.fill_buf().map_err(TspParsingError::ReadFailed)?;
+
+    match Compression::detect(start) {
+        None => Ok(Box::new(reader)),
+        #[cfg(feature = "gzip")]
+        Some(Compression::Gzip) => Ok(Box::new(BufReader::new(
+            flate2::bufread::MultiGzDecoder::new(reader),
+        ))),
+        #[cfg(feature = "bzip2")]
+        Some(Compression::Bzip2) => Ok(Box::new(BufReader::new(
+            bzip2::bufread::MultiBzDecoder::new(reader),
+        ))),
+        #[allow(unreachable_patterns)]
+        Some(compression) => Err(TspParsingError::CompressionNotSupported(compression)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magic_bytes_are_detected() {
+        assert_eq!(
+            Some(Compression::Gzip),
+            Compression::detect(&[0x1f, 0x8b, 8, 0])
+        );
+        assert_eq!(Some(Compression::Bzip2), Compression::detect(b"BZh91AY&SY"));
+        assert_eq!(None, Compression::detect(b"NAME: gr17"));
+        assert_eq!(None, Compression::detect(&[0x1f]));
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    #[test]
+    fn gzip_needs_feature() {
+        let result = decompress(&[0x1f, 0x8b, 8, 0][..]);
+
+        assert!(matches!(
+            result,
+            Err(TspParsingError::CompressionNotSupported(Compression::Gzip))
+        ));
+    }
+}
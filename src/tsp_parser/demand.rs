@@ -0,0 +1,94 @@
+use std::io::BufRead;
+
+use crate::tsp_parser::tokens::Tokens;
+use crate::tsp_parser::TspParsingError;
+
+/// Reads `node demand` pairs of DEMAND_SECTION, nodes may come in any order.
+pub fn parse_demands<R: BufRead>(
+    tokens: &mut Tokens<R>,
+    dimension: usize,
+) -> Result<Vec<u32>, TspParsingError> {
+    let mut demands = vec![0; dimension];
+
+    for _ in 0..dimension {
+        let node =
+            parse_node(tokens, dimension)?.ok_or_else(|| TspParsingError::NotEnoughData {
+                section: tokens.section().to_string(),
+                line: tokens.line_number(),
+            })?;
+
+        demands[node] = tokens.expect_number(TspParsingError::DemandNotANumber)?;
+    }
+
+    Ok(demands)
+}
+
+/// Reads nodes of DEPOT_SECTION until the closing -1.
+pub fn parse_depots<R: BufRead>(
+    tokens: &mut Tokens<R>,
+    dimension: usize,
+) -> Result<Vec<usize>, TspParsingError> {
+    let mut depots = Vec::new();
+
+    while let Some(depot) = parse_node(tokens, dimension)? {
+        depots.push(depot);
+    }
+
+    Ok(depots)
+}
+
+// 1-based node number turned 0-based, `None` at -1 or the end of the section
+fn parse_node<R: BufRead>(
+    tokens: &mut Tokens<R>,
+    dimension: usize,
+) -> Result<Option<usize>, TspParsingError> {
+    let context = tokens.peek_context()?;
+
+    match tokens.next_number::<i64, _>(TspParsingError::NodeNotANumber)? {
+        None | Some(-1) => Ok(None),
+        Some(node) if node >= 1 && node as usize <= dimension => Ok(Some(node as usize - 1)),
+        Some(_) => Err(TspParsingError::NodeOutOfRange(
+            context.expect("number was just read"),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn demands_parser_working() {
+        let mut tokens = Tokens::new("1 0\n3 7\n2 5\nDEPOT_SECTION".as_bytes());
+
+        let demands = parse_demands(&mut tokens, 3).expect("error while parsing data");
+
+        assert_eq!(vec![0, 5, 7], demands);
+    }
+
+    #[test]
+    fn depots_parser_working() {
+        let mut tokens = Tokens::new("1\n-1\nEOF".as_bytes());
+
+        let depots = parse_depots(&mut tokens, 3).expect("error while parsing data");
+
+        assert_eq!(vec![0], depots);
+        assert_eq!(Some(String::from("EOF")), tokens.next_section().unwrap());
+    }
+
+    #[test]
+    fn demand_not_a_number() {
+        let mut tokens = Tokens::new("1 0\n2 x".as_bytes());
+        tokens.set_section("DEMAND_SECTION");
+
+        match parse_demands(&mut tokens, 2) {
+            Err(TspParsingError::DemandNotANumber(context)) => {
+                assert_eq!(
+                    (2, 3, "x"),
+                    (context.line, context.column, &context.token[..])
+                )
+            }
+            result => panic!("expected DemandNotANumber, got {:?}", result),
+        }
+    }
+}
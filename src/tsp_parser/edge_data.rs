@@ -0,0 +1,115 @@
+use std::io::BufRead;
+
+use crate::tsp_parser::tokens::Tokens;
+use crate::tsp_parser::TspParsingError;
+
+/// Reads 1-based node pairs until the closing -1, the format of both
+/// FIXED_EDGES_SECTION and EDGE_LIST in EDGE_DATA_SECTION.
+pub fn parse_edge_list<R: BufRead>(
+    tokens: &mut Tokens<R>,
+    dimension: usize,
+) -> Result<Vec<(usize, usize)>, TspParsingError> {
+    let mut edges = Vec::new();
+
+    while let Some(from) = parse_node(tokens, dimension, false)? {
+        let to =
+            parse_node(tokens, dimension, true)?.ok_or_else(|| TspParsingError::NotEnoughData {
+                section: tokens.section().to_string(),
+                line: tokens.line_number(),
+            })?;
+
+        edges.push((from, to));
+    }
+
+    Ok(edges)
+}
+
+/// Reads ADJ_LIST of EDGE_DATA_SECTION, every node is followed by its
+/// neighbours and -1, another -1 closes the whole list.
+pub fn parse_adj_list<R: BufRead>(
+    tokens: &mut Tokens<R>,
+    dimension: usize,
+) -> Result<Vec<(usize, usize)>, TspParsingError> {
+    let mut edges = Vec::new();
+
+    while let Some(from) = parse_node(tokens, dimension, false)? {
+        while let Some(to) = parse_node(tokens, dimension, false)? {
+            edges.push((from, to));
+        }
+    }
+
+    Ok(edges)
+}
+
+// `None` once the section ends, the terminator is only allowed between edges
+fn parse_node<R: BufRead>(
+    tokens: &mut Tokens<R>,
+    dimension: usize,
+    inside_edge: bool,
+) -> Result<Option<usize>, TspParsingError> {
+    let context = tokens.peek_context()?;
+
+    match tokens.next_number::<i64, _>(TspParsingError::NodeNotANumber)? {
+        None => Ok(None),
+        Some(-1) if !inside_edge => Ok(None),
+        Some(node) if node >= 1 && node as usize <= dimension => Ok(Some(node as usize - 1)),
+        Some(_) => Err(TspParsingError::NodeOutOfRange(
+            context.expect("number was just read"),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edge_list_parser_working() {
+        let mut tokens = Tokens::new("1 2\n4 3\n-1\nEOF".as_bytes());
+
+        let edges = parse_edge_list(&mut tokens, 4).expect("error while parsing data");
+
+        assert_eq!(vec![(0, 1), (3, 2)], edges);
+        assert_eq!(Some(String::from("EOF")), tokens.next_section().unwrap());
+    }
+
+    #[test]
+    fn edge_list_node_out_of_range() {
+        let mut tokens = Tokens::new("1 2\n3 5\n-1".as_bytes());
+        tokens.set_section("FIXED_EDGES_SECTION");
+
+        match parse_edge_list(&mut tokens, 4) {
+            Err(TspParsingError::NodeOutOfRange(context)) => {
+                assert_eq!(
+                    (2, 3, "5"),
+                    (context.line, context.column, &context.token[..])
+                )
+            }
+            result => panic!("expected NodeOutOfRange, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn edge_list_half_edge() {
+        let mut tokens = Tokens::new("1 2\n3".as_bytes());
+
+        assert!(matches!(
+            parse_edge_list(&mut tokens, 4),
+            Err(TspParsingError::NotEnoughData { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn adj_list_parser_working() {
+        let mut tokens = Tokens::new("1 2 3 -1
+2 3
+-1
+-1
+EOF".as_bytes());
+
+        let edges = parse_adj_list(&mut tokens, 3).expect("error while parsing data");
+
+        assert_eq!(vec![(0, 1), (0, 2), (1, 2)], edges);
+        assert_eq!(Some(String::from("EOF")), tokens.next_section().unwrap());
+    }
+}
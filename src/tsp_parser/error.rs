@@ -0,0 +1,149 @@
+use std::fmt;
+
+use crate::tsp::FixedEdgesError;
+use crate::tsp_parser::Compression;
+
+/// Where a malformed token was found.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TokenContext {
+    pub line: usize,
+    pub column: usize,
+    pub token: String,
+    pub section: String,
+}
+
+impl fmt::Display for TokenContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "`{}` at line {}, column {} of {}",
+            self.token, self.line, self.column, self.section
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum TspParsingError {
+    FileDoesntExist(std::io::Error),
+    ReadFailed(std::io::Error),
+    CacheFailed(std::io::Error),
+    CompressionNotSupported(Compression),
+    NoExplicitFileType,
+    NoFileType,
+    NoDimension,
+    UnknownExplicitFileType(TokenContext),
+    UnknownFileType(TokenContext),
+    UnknownProblemType(TokenContext),
+    UnknownEdgeDataFormat(TokenContext),
+    NoEdgeDataFormat,
+    NotEnoughData {
+        section: String,
+        line: usize,
+    },
+    DimensionNotANumber(TokenContext),
+    CapacityNotANumber(TokenContext),
+    WeightNotANumber(TokenContext),
+    CoordinateNotANumber(TokenContext),
+    NodeNotANumber(TokenContext),
+    NodeOutOfRange(TokenContext),
+    InvalidFixedEdges(FixedEdgesError),
+    NoTourSection,
+    NoCapacity,
+    NoDemands,
+    NoDepot,
+    DemandNotANumber(TokenContext),
+    MissingWeight {
+        row: usize,
+        column: usize,
+        line: usize,
+    },
+    TooManyWeights {
+        expected: usize,
+        context: TokenContext,
+    },
+}
+
+impl fmt::Display for TspParsingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TspParsingError::FileDoesntExist(error) => write!(f, "couldn't open file: {}", error),
+            TspParsingError::ReadFailed(error) => write!(f, "couldn't read input: {}", error),
+            TspParsingError::CacheFailed(error) => write!(f, "couldn't write cache: {}", error),
+            TspParsingError::CompressionNotSupported(compression) => write!(
+                f,
+                "input is {} compressed, enable the `{}` feature to read it",
+                compression,
+                compression.feature()
+            ),
+            TspParsingError::NoExplicitFileType => write!(f, "missing EDGE_WEIGHT_FORMAT"),
+            TspParsingError::NoFileType => write!(f, "missing EDGE_WEIGHT_TYPE"),
+            TspParsingError::NoDimension => write!(f, "missing DIMENSION"),
+            TspParsingError::UnknownExplicitFileType(context) => {
+                write!(f, "unsupported EDGE_WEIGHT_FORMAT {}", context)
+            }
+            TspParsingError::UnknownFileType(context) => {
+                write!(f, "unsupported EDGE_WEIGHT_TYPE {}", context)
+            }
+            TspParsingError::UnknownProblemType(context) => {
+                write!(f, "unknown TYPE {}", context)
+            }
+            TspParsingError::UnknownEdgeDataFormat(context) => {
+                write!(f, "unsupported EDGE_DATA_FORMAT {}", context)
+            }
+            TspParsingError::NoEdgeDataFormat => write!(f, "missing EDGE_DATA_FORMAT"),
+            TspParsingError::NotEnoughData { section, line } => {
+                write!(f, "{} ended early at line {}", section, line)
+            }
+            TspParsingError::DimensionNotANumber(context) => {
+                write!(f, "DIMENSION is not a number: {}", context)
+            }
+            TspParsingError::CapacityNotANumber(context) => {
+                write!(f, "CAPACITY is not a number: {}", context)
+            }
+            TspParsingError::WeightNotANumber(context) => {
+                write!(f, "weight is not a number: {}", context)
+            }
+            TspParsingError::CoordinateNotANumber(context) => {
+                write!(f, "coordinate is not a number: {}", context)
+            }
+            TspParsingError::NodeNotANumber(context) => {
+                write!(f, "node is not a number: {}", context)
+            }
+            TspParsingError::NodeOutOfRange(context) => {
+                write!(f, "node doesn't exist: {}", context)
+            }
+            TspParsingError::InvalidFixedEdges(error) => {
+                write!(f, "invalid FIXED_EDGES_SECTION: {}", error)
+            }
+            TspParsingError::NoTourSection => write!(f, "missing TOUR_SECTION"),
+            TspParsingError::NoCapacity => write!(f, "missing CAPACITY"),
+            TspParsingError::NoDemands => write!(f, "missing DEMAND_SECTION"),
+            TspParsingError::NoDepot => write!(f, "missing DEPOT_SECTION"),
+            TspParsingError::DemandNotANumber(context) => {
+                write!(f, "demand is not a number: {}", context)
+            }
+            TspParsingError::MissingWeight { row, column, line } => write!(
+                f,
+                "missing weight of edge ({}, {}), data ended at line {}",
+                row, column, line
+            ),
+            TspParsingError::TooManyWeights { expected, context } => write!(
+                f,
+                "expected only {} weights, found another {}",
+                expected, context
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TspParsingError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TspParsingError::FileDoesntExist(error)
+            | TspParsingError::ReadFailed(error)
+            | TspParsingError::CacheFailed(error) => Some(error),
+            TspParsingError::InvalidFixedEdges(error) => Some(error),
+            _ => None,
+        }
+    }
+}
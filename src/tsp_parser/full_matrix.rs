@@ -1,43 +1,29 @@
-use std::str::Lines;
+use std::io::BufRead;
 
+use crate::distances::Distances;
+use crate::matrix::DenseMatrix;
+use crate::weight::Weight;
+use crate::tsp_parser::tokens::Tokens;
 use crate::tsp_parser::{VariantParser, TspParsingError};
 
 pub struct FullMatrixTspParser;
 
 impl VariantParser for FullMatrixTspParser {
-    fn parse(file_lines: &mut Lines, dimension: usize) -> Result<Vec<Vec<u32>>, TspParsingError> {
-        let mut edges = Vec::new();
+    fn parse<R: BufRead, W: Weight>(
+        tokens: &mut Tokens<R>,
+        dimension: usize,
+    ) -> Result<Distances<W>, TspParsingError> {
+        let mut edges = DenseMatrix::new(dimension);
 
-        let mut curr_line = file_lines.next().ok_or(TspParsingError::NotEnoughData)?;
+        for from in 0..dimension {
+            for to in 0..dimension {
+                let edge = tokens.expect_number(TspParsingError::WeightNotANumber)?;
 
-        let mut line_weights = curr_line.split_whitespace();
-
-        for _ in 0..dimension {
-            let mut curr_edges = Vec::new();
-
-            let mut edge_counter = 0;
-
-            while edge_counter < dimension {
-                match line_weights.next() {
-                    Some(edge) => {
-                        let edge = edge
-                            .parse()
-                            .map_err(|_| TspParsingError::WeightNotANumber)?;
-
-                        curr_edges.push(edge);
-                        edge_counter += 1;
-                    }
-                    None => {
-                        curr_line = file_lines.next().ok_or(TspParsingError::NotEnoughData)?;
-                        line_weights = curr_line.split_whitespace();
-                    }
-                }
+                edges.set(from, to, edge);
             }
-
-            edges.push(curr_edges);
         }
 
-        Ok(edges)
+        Ok(Distances::Matrix(edges))
     }
 }
 
@@ -51,13 +37,26 @@ mod tests {
     2 9999    3
     3    3 9999";
 
-        let mut data_lines = data.lines();
+        let mut tokens = Tokens::new(data.as_bytes());
 
-        let edges =
-            FullMatrixTspParser::parse(&mut data_lines, 3).expect("error while parsing data");
+        let edges: Distances =
+            FullMatrixTspParser::parse(&mut tokens, 3).expect("error while parsing data");
         assert_eq!(
             vec![vec![9999, 2, 3], vec![2, 9999, 3], vec![3, 3, 9999]],
-            edges
+            edges.to_matrix()
         );
     }
+
+    #[test]
+    fn full_matrix_parser_not_enough_data() {
+        let mut tokens = Tokens::new("1 2 3\n4 5 6\n7 8\nEOF".as_bytes());
+        tokens.set_section("EDGE_WEIGHT_SECTION");
+
+        let edges: Result<Distances, _> = FullMatrixTspParser::parse(&mut tokens, 3);
+
+        assert!(matches!(
+            edges,
+            Err(TspParsingError::NotEnoughData { line: 4, ref section }) if section == "EDGE_WEIGHT_SECTION"
+        ));
+    }
 }
@@ -0,0 +1,307 @@
+use std::io::BufRead;
+
+use crate::tsp_parser::tokens::Tokens;
+use crate::tsp_parser::{TokenContext, TspFileType, TspParsingError};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ProblemType {
+    Tsp,
+    Atsp,
+    Sop,
+    Hcp,
+    Cvrp,
+    Tour,
+}
+
+impl ProblemType {
+    pub(crate) fn from_keyword(keyword: &str) -> Option<ProblemType> {
+        match keyword {
+            "TSP" => Some(ProblemType::Tsp),
+            "ATSP" => Some(ProblemType::Atsp),
+            "SOP" => Some(ProblemType::Sop),
+            "HCP" => Some(ProblemType::Hcp),
+            "CVRP" => Some(ProblemType::Cvrp),
+            "TOUR" => Some(ProblemType::Tour),
+            _ => None,
+        }
+    }
+
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            ProblemType::Tsp => "TSP",
+            ProblemType::Atsp => "ATSP",
+            ProblemType::Sop => "SOP",
+            ProblemType::Hcp => "HCP",
+            ProblemType::Cvrp => "CVRP",
+            ProblemType::Tour => "TOUR",
+        }
+    }
+}
+
+/// Specification part of a TSPLIB file, everything before the first section.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct TspHeader {
+    pub name: Option<String>,
+    pub problem_type: Option<ProblemType>,
+    pub comment: Option<String>,
+    pub dimension: usize,
+    pub edge_weight_type: Option<String>,
+    pub edge_weight_format: Option<String>,
+    pub edge_data_format: Option<String>,
+    pub display_data_type: Option<String>,
+    pub capacity: Option<u32>,
+}
+
+impl TspHeader {
+    /// Reads `KEY: value` (or `KEY : value`) lines in any order until the first
+    /// section keyword, which is consumed and returned together with the header.
+    pub fn parse<R: BufRead>(
+        tokens: &mut Tokens<R>,
+    ) -> Result<(TspHeader, String), TspParsingError> {
+        let mut header = TspHeader::default();
+        let mut dimension = None;
+
+        let section = loop {
+            let line = match tokens.next_line()? {
+                Some(line) => line.to_string(),
+                None => break String::from("EOF"),
+            };
+
+            let (key, value, value_column) = match line.find(':') {
+                Some(colon) => {
+                    let value = line[colon + 1..].trim_start();
+
+                    (
+                        line[..colon].trim(),
+                        value.trim_end(),
+                        line.len() - value.len() + 1,
+                    )
+                }
+                None if line.trim().is_empty() => continue,
+                None => break line.trim().to_string(),
+            };
+
+            let context = || TokenContext {
+                line: tokens.line_number(),
+                column: value_column,
+                token: value.to_string(),
+                section: key.to_string(),
+            };
+
+            match key {
+                "NAME" => header.name = Some(value.to_string()),
+                "TYPE" => {
+                    header.problem_type = Some(
+                        ProblemType::from_keyword(value)
+                            .ok_or_else(|| TspParsingError::UnknownProblemType(context()))?,
+                    )
+                }
+                "COMMENT" => {
+                    header.comment = match header.comment.take() {
+                        Some(comment) => Some(format!("{}\n{}", comment, value)),
+                        None => Some(value.to_string()),
+                    }
+                }
+                "DIMENSION" => {
+                    dimension = Some(
+                        value
+                            .parse()
+                            .map_err(|_| TspParsingError::DimensionNotANumber(context()))?,
+                    )
+                }
+                "EDGE_WEIGHT_TYPE" => {
+                    if value != "EXPLICIT" && coordinate_file_type(value).is_none() {
+                        return Err(TspParsingError::UnknownFileType(context()));
+                    }
+
+                    header.edge_weight_type = Some(value.to_string())
+                }
+                "EDGE_WEIGHT_FORMAT" => {
+                    if explicit_file_type(value).is_none() {
+                        return Err(TspParsingError::UnknownExplicitFileType(context()));
+                    }
+
+                    header.edge_weight_format = Some(value.to_string())
+                }
+                "EDGE_DATA_FORMAT" => {
+                    if value != "EDGE_LIST" && value != "ADJ_LIST" {
+                        return Err(TspParsingError::UnknownEdgeDataFormat(context()));
+                    }
+
+                    header.edge_data_format = Some(value.to_string())
+                }
+                "DISPLAY_DATA_TYPE" => header.display_data_type = Some(value.to_string()),
+                "CAPACITY" => {
+                    header.capacity = Some(
+                        value
+                            .parse()
+                            .map_err(|_| TspParsingError::CapacityNotANumber(context()))?,
+                    )
+                }
+                _ => {}
+            }
+        };
+
+        header.dimension = dimension.ok_or(TspParsingError::NoDimension)?;
+
+        Ok((header, section))
+    }
+
+    pub fn file_type(&self) -> Result<TspFileType, TspParsingError> {
+        let edge_weight_type = self
+            .edge_weight_type
+            .as_deref()
+            .ok_or(TspParsingError::NoFileType)?;
+
+        if edge_weight_type != "EXPLICIT" {
+            return coordinate_file_type(edge_weight_type).ok_or(TspParsingError::NoFileType);
+        }
+
+        self.edge_weight_format
+            .as_deref()
+            .and_then(explicit_file_type)
+            .ok_or(TspParsingError::NoExplicitFileType)
+    }
+}
+
+fn coordinate_file_type(edge_weight_type: &str) -> Option<TspFileType> {
+    match edge_weight_type {
+        "EUC_2D" => Some(TspFileType::Euc2d),
+        "EUC_3D" => Some(TspFileType::Euc3d),
+        "CEIL_2D" => Some(TspFileType::Ceil2d),
+        "MAN_2D" => Some(TspFileType::Man2d),
+        "MAX_2D" => Some(TspFileType::Max2d),
+        "GEO" => Some(TspFileType::Geo),
+        "ATT" => Some(TspFileType::Att),
+        _ => None,
+    }
+}
+
+fn explicit_file_type(edge_weight_format: &str) -> Option<TspFileType> {
+    match edge_weight_format {
+        "FULL_MATRIX" => Some(TspFileType::FullMatrix),
+        "UPPER_ROW" => Some(TspFileType::UpperRow),
+        "LOWER_ROW" => Some(TspFileType::LowerRow),
+        "UPPER_DIAG_ROW" => Some(TspFileType::UpperDiagRow),
+        "LOWER_DIAG_ROW" => Some(TspFileType::LowerDiagRow),
+        "UPPER_COL" => Some(TspFileType::UpperCol),
+        "LOWER_COL" => Some(TspFileType::LowerCol),
+        "UPPER_DIAG_COL" => Some(TspFileType::UpperDiagCol),
+        "LOWER_DIAG_COL" => Some(TspFileType::LowerDiagCol),
+        _ => None,
+    }
+}
+
+impl TspFileType {
+    pub fn edge_weight_type(&self) -> &'static str {
+        match self {
+            TspFileType::Euc2d => "EUC_2D",
+            TspFileType::Euc3d => "EUC_3D",
+            TspFileType::Ceil2d => "CEIL_2D",
+            TspFileType::Man2d => "MAN_2D",
+            TspFileType::Max2d => "MAX_2D",
+            TspFileType::Geo => "GEO",
+            TspFileType::Att => "ATT",
+            _ => "EXPLICIT",
+        }
+    }
+
+    pub fn edge_weight_format(&self) -> Option<&'static str> {
+        match self {
+            TspFileType::FullMatrix => Some("FULL_MATRIX"),
+            TspFileType::UpperRow => Some("UPPER_ROW"),
+            TspFileType::LowerRow => Some("LOWER_ROW"),
+            TspFileType::UpperDiagRow => Some("UPPER_DIAG_ROW"),
+            TspFileType::LowerDiagRow => Some("LOWER_DIAG_ROW"),
+            TspFileType::UpperCol => Some("UPPER_COL"),
+            TspFileType::LowerCol => Some("LOWER_COL"),
+            TspFileType::UpperDiagCol => Some("UPPER_DIAG_COL"),
+            TspFileType::LowerDiagCol => Some("LOWER_DIAG_COL"),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_keys_in_any_order() {
+        let data = "EDGE_WEIGHT_FORMAT : LOWER_DIAG_ROW
+COMMENT: first line
+TYPE : TSP
+EDGE_WEIGHT_TYPE:EXPLICIT
+COMMENT : second line
+DIMENSION :  3
+NAME: test
+DISPLAY_DATA_TYPE: TWOD_DISPLAY
+EDGE_WEIGHT_SECTION
+ 0 2 0 3 3 0";
+
+        let mut tokens = Tokens::new(data.as_bytes());
+
+        let (header, section) = TspHeader::parse(&mut tokens).expect("error while parsing");
+
+        assert_eq!("EDGE_WEIGHT_SECTION", section);
+        assert_eq!(
+            Some(" 0 2 0 3 3 0"),
+            tokens.next_line().expect("input is valid")
+        );
+        assert_eq!(
+            TspHeader {
+                name: Some("test".to_string()),
+                problem_type: Some(ProblemType::Tsp),
+                comment: Some("first line\nsecond line".to_string()),
+                dimension: 3,
+                edge_weight_type: Some("EXPLICIT".to_string()),
+                edge_weight_format: Some("LOWER_DIAG_ROW".to_string()),
+                edge_data_format: None,
+                display_data_type: Some("TWOD_DISPLAY".to_string()),
+                capacity: None,
+            },
+            header
+        );
+        assert!(matches!(header.file_type(), Ok(TspFileType::LowerDiagRow)));
+    }
+
+    #[test]
+    fn header_without_dimension() {
+        let mut tokens =
+            Tokens::new("NAME: test\nEDGE_WEIGHT_TYPE: EUC_2D\nNODE_COORD_SECTION".as_bytes());
+
+        let header = TspHeader::parse(&mut tokens);
+
+        assert!(matches!(header, Err(TspParsingError::NoDimension)));
+    }
+
+    #[test]
+    fn header_with_capacity() {
+        let mut tokens = Tokens::new("TYPE: CVRP\nDIMENSION: 2\nCAPACITY: 100\nEOF".as_bytes());
+
+        let (header, section) = TspHeader::parse(&mut tokens).expect("error while parsing");
+
+        assert_eq!("EOF", section);
+        assert_eq!(Some(ProblemType::Cvrp), header.problem_type);
+        assert_eq!(Some(100), header.capacity);
+    }
+
+    #[test]
+    fn unknown_edge_weight_type_has_context() {
+        let mut tokens =
+            Tokens::new("NAME: test\nEDGE_WEIGHT_TYPE :  XRAY1\nDIMENSION: 3".as_bytes());
+
+        match TspHeader::parse(&mut tokens) {
+            Err(TspParsingError::UnknownFileType(context)) => assert_eq!(
+                TokenContext {
+                    line: 2,
+                    column: 21,
+                    token: "XRAY1".to_string(),
+                    section: "EDGE_WEIGHT_TYPE".to_string(),
+                },
+                context
+            ),
+            _ => panic!("expected UnknownFileType"),
+        }
+    }
+}
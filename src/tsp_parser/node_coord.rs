@@ -0,0 +1,201 @@
+use std::io::BufRead;
+
+use crate::distances::{Distances, Metric};
+use crate::tsp_parser::tokens::Tokens;
+use crate::tsp_parser::{VariantParser, TspParsingError};
+use crate::weight::Weight;
+
+pub struct Euc2dTspParser;
+
+impl VariantParser for Euc2dTspParser {
+    fn parse<R: BufRead, W: Weight>(
+        tokens: &mut Tokens<R>,
+        dimension: usize,
+    ) -> Result<Distances<W>, TspParsingError> {
+        parse_coords_2d(tokens, dimension, Metric::Euc2d)
+    }
+}
+
+pub struct Ceil2dTspParser;
+
+impl VariantParser for Ceil2dTspParser {
+    fn parse<R: BufRead, W: Weight>(
+        tokens: &mut Tokens<R>,
+        dimension: usize,
+    ) -> Result<Distances<W>, TspParsingError> {
+        parse_coords_2d(tokens, dimension, Metric::Ceil2d)
+    }
+}
+
+pub struct Man2dTspParser;
+
+impl VariantParser for Man2dTspParser {
+    fn parse<R: BufRead, W: Weight>(
+        tokens: &mut Tokens<R>,
+        dimension: usize,
+    ) -> Result<Distances<W>, TspParsingError> {
+        parse_coords_2d(tokens, dimension, Metric::Man2d)
+    }
+}
+
+pub struct Max2dTspParser;
+
+impl VariantParser for Max2dTspParser {
+    fn parse<R: BufRead, W: Weight>(
+        tokens: &mut Tokens<R>,
+        dimension: usize,
+    ) -> Result<Distances<W>, TspParsingError> {
+        parse_coords_2d(tokens, dimension, Metric::Max2d)
+    }
+}
+
+pub struct GeoTspParser;
+
+impl VariantParser for GeoTspParser {
+    fn parse<R: BufRead, W: Weight>(
+        tokens: &mut Tokens<R>,
+        dimension: usize,
+    ) -> Result<Distances<W>, TspParsingError> {
+        parse_coords_2d(tokens, dimension, Metric::Geo)
+    }
+}
+
+pub struct AttTspParser;
+
+impl VariantParser for AttTspParser {
+    fn parse<R: BufRead, W: Weight>(
+        tokens: &mut Tokens<R>,
+        dimension: usize,
+    ) -> Result<Distances<W>, TspParsingError> {
+        parse_coords_2d(tokens, dimension, Metric::Att)
+    }
+}
+
+pub struct Euc3dTspParser;
+
+impl VariantParser for Euc3dTspParser {
+    fn parse<R: BufRead, W: Weight>(
+        tokens: &mut Tokens<R>,
+        dimension: usize,
+    ) -> Result<Distances<W>, TspParsingError> {
+        let mut coords = Vec::with_capacity(dimension);
+
+        for _ in 0..dimension {
+            let [x, y, z] = parse_node_coords(tokens)?;
+
+            coords.push((x, y, z));
+        }
+
+        Ok(Distances::Coordinates3d(coords))
+    }
+}
+
+fn parse_coords_2d<R: BufRead, W: Weight>(
+    tokens: &mut Tokens<R>,
+    dimension: usize,
+    metric: Metric,
+) -> Result<Distances<W>, TspParsingError> {
+    Ok(Distances::Coordinates(
+        parse_positions(tokens, dimension)?,
+        metric,
+    ))
+}
+
+/// Two dimensional node positions, shared by NODE_COORD_SECTION and
+/// DISPLAY_DATA_SECTION.
+pub fn parse_positions<R: BufRead>(
+    tokens: &mut Tokens<R>,
+    dimension: usize,
+) -> Result<Vec<(f64, f64)>, TspParsingError> {
+    let mut coords = Vec::with_capacity(dimension);
+
+    for _ in 0..dimension {
+        let [x, y] = parse_node_coords(tokens)?;
+
+        coords.push((x, y));
+    }
+
+    Ok(coords)
+}
+
+// every node is given as its number followed by the coordinates
+fn parse_node_coords<R: BufRead, const N: usize>(
+    tokens: &mut Tokens<R>,
+) -> Result<[f64; N], TspParsingError> {
+    tokens.expect_number::<usize, _>(TspParsingError::NodeNotANumber)?;
+
+    let mut coords = [0.0; N];
+
+    for coord in coords.iter_mut() {
+        *coord = tokens.expect_number(TspParsingError::CoordinateNotANumber)?;
+    }
+
+    Ok(coords)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn euc2d_parser_working() {
+        let data = "1 0.0 10.0
+2 0.0 0.0
+3 5.0 5.0";
+
+        let mut tokens = Tokens::new(data.as_bytes());
+
+        let edges: Distances =
+            Euc2dTspParser::parse(&mut tokens, 3).expect("error while parsing data");
+
+        assert_eq!(
+            vec![vec![0, 10, 7], vec![10, 0, 7], vec![7, 7, 0]],
+            edges.to_matrix()
+        );
+    }
+
+    #[test]
+    fn euc3d_parser_working() {
+        let data = "1 0.0 0.0 0.0
+2 1.0 2.0 3.0";
+
+        let mut tokens = Tokens::new(data.as_bytes());
+
+        let edges: Distances =
+            Euc3dTspParser::parse(&mut tokens, 2).expect("error while parsing data");
+
+        assert_eq!(vec![vec![0, 4], vec![4, 0]], edges.to_matrix());
+    }
+
+    #[test]
+    fn euc2d_parser_coordinate_not_a_number() {
+        let mut tokens = Tokens::new("1 0.0 10.0\n2 0.0 O.5".as_bytes());
+        tokens.set_section("NODE_COORD_SECTION");
+
+        match Euc2dTspParser::parse::<_, u32>(&mut tokens, 2) {
+            Err(TspParsingError::CoordinateNotANumber(context)) => {
+                assert_eq!(
+                    (2, 7, "O.5"),
+                    (context.line, context.column, &context.token[..])
+                )
+            }
+            _ => panic!("expected CoordinateNotANumber"),
+        }
+    }
+
+    #[test]
+    fn euc2d_parser_node_not_a_number() {
+        let mut tokens = Tokens::new("1 0.0 10.0\nx2 0.0 0.5".as_bytes());
+        tokens.set_section("NODE_COORD_SECTION");
+
+        match Euc2dTspParser::parse::<_, u32>(&mut tokens, 2) {
+            Err(TspParsingError::NodeNotANumber(context)) => {
+                assert_eq!(
+                    (2, 1, "x2"),
+                    (context.line, context.column, &context.token[..])
+                )
+            }
+            _ => panic!("expected NodeNotANumber"),
+        }
+    }
+}
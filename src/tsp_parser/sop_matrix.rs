@@ -0,0 +1,112 @@
+use std::io::BufRead;
+
+use crate::distances::Distances;
+use crate::matrix::DenseMatrix;
+use crate::tsp_parser::tokens::Tokens;
+use crate::tsp_parser::TspParsingError;
+use crate::weight::Weight;
+
+// pairs (before, after) of vertices
+type Precedences = Vec<(usize, usize)>;
+
+/// FULL_MATRIX of a SOP file, where -1 at row i, column j means that j has
+/// to be visited before i. Such edges can't be used, so they weigh 0.
+///
+/// TSPLIB SOP files repeat the dimension in front of the matrix, it's
+/// skipped when present since the matrix itself always starts with 0.
+pub fn parse_sop_matrix<R: BufRead, W: Weight>(
+    tokens: &mut Tokens<R>,
+    dimension: usize,
+) -> Result<(Distances<W>, Precedences), TspParsingError> {
+    let mut edges = DenseMatrix::new(dimension);
+    let mut precedences = Vec::new();
+
+    let dimension_text = dimension.to_string();
+
+    if dimension != 0
+        && tokens
+            .peek_token()?
+            .is_some_and(|token| token.text == dimension_text)
+    {
+        tokens.next_token()?;
+    }
+
+    for from in 0..dimension {
+        for to in 0..dimension {
+            let context = tokens.peek_context()?;
+
+            if context
+                .as_ref()
+                .is_some_and(|context| context.token == "-1")
+            {
+                tokens.next_token()?;
+                precedences.push((to, from));
+
+                continue;
+            }
+
+            let weight: W = tokens.expect_number(TspParsingError::WeightNotANumber)?;
+
+            if weight < W::ZERO {
+                return Err(TspParsingError::WeightNotANumber(
+                    context.expect("number was just read"),
+                ));
+            }
+
+            edges.set(from, to, weight);
+        }
+    }
+
+    Ok((Distances::Matrix(edges), precedences))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sop_matrix_parser_working() {
+        let data = "3
+0 5 100
+-1 0 7
+-1 -1 0";
+
+        let mut tokens = Tokens::new(data.as_bytes());
+
+        let (distances, precedences): (Distances, _) =
+            parse_sop_matrix(&mut tokens, 3).expect("error while parsing data");
+
+        assert_eq!(
+            vec![vec![0, 5, 100], vec![0, 0, 7], vec![0, 0, 0]],
+            distances.to_matrix()
+        );
+        assert_eq!(vec![(0, 1), (0, 2), (1, 2)], precedences);
+    }
+
+    #[test]
+    fn sop_matrix_without_dimension() {
+        let mut tokens = Tokens::new("0 5\n-1 0".as_bytes());
+
+        let (distances, precedences): (Distances, _) =
+            parse_sop_matrix(&mut tokens, 2).expect("error while parsing data");
+
+        assert_eq!(vec![vec![0, 5], vec![0, 0]], distances.to_matrix());
+        assert_eq!(vec![(0, 1)], precedences);
+    }
+
+    #[test]
+    fn sop_matrix_negative_weight() {
+        let mut tokens = Tokens::new("2\n0 -2\n-1 0".as_bytes());
+        tokens.set_section("EDGE_WEIGHT_SECTION");
+
+        match parse_sop_matrix::<_, u32>(&mut tokens, 2) {
+            Err(TspParsingError::WeightNotANumber(context)) => {
+                assert_eq!(
+                    (2, 3, "-2"),
+                    (context.line, context.column, &context.token[..])
+                )
+            }
+            result => panic!("expected WeightNotANumber, got {:?}", result),
+        }
+    }
+}
@@ -2,23 +2,36 @@ use super::*;
 use crate::*;
 
 use std::fs::File;
+use std::io::BufReader;
 
 #[test]
 fn check_test_files_exist() -> std::io::Result<()> {
     File::open("euc_2d")?;
     File::open("lower_diag_row")?;
+    File::open("lower_diag_row_zeros")?;
+    File::open("lower_diag_row_short")?;
     File::open("full_matrix")?;
+    File::open("ceil_2d")?;
+    File::open("euc_3d")?;
+    File::open("man_2d")?;
+    File::open("max_2d")?;
+    File::open("upper_row")?;
+    File::open("lower_row")?;
+    File::open("upper_diag_row")?;
+    File::open("upper_col")?;
+    File::open("lower_col")?;
+    File::open("upper_diag_col")?;
+    File::open("lower_diag_col")?;
 
     Ok(())
 }
 
 fn check_file_type_works(filename: &str, file_type: TspFileType) {
-    let file_content = std::fs::read_to_string(filename).expect("file doesn't exist");
-    let mut lines = file_content.lines();
+    let file = File::open(filename).expect("file doesn't exist");
+    let mut tokens = Tokens::new(BufReader::new(file));
 
-    lines.nth(3);
-
-    let tsp_type = TspParser::check_file_type(&mut lines).expect("file couldn't be parsed");
+    let (header, _) = TspHeader::parse(&mut tokens).expect("file couldn't be parsed");
+    let tsp_type = header.file_type().expect("file couldn't be parsed");
     assert_eq!(file_type, tsp_type);
 }
 
@@ -27,6 +40,26 @@ fn check_euc_2d_file_type_works() {
     check_file_type_works("euc_2d", TspFileType::Euc2d);
 }
 
+#[test]
+fn check_ceil_2d_file_type_works() {
+    check_file_type_works("ceil_2d", TspFileType::Ceil2d);
+}
+
+#[test]
+fn check_euc_3d_file_type_works() {
+    check_file_type_works("euc_3d", TspFileType::Euc3d);
+}
+
+#[test]
+fn check_man_2d_file_type_works() {
+    check_file_type_works("man_2d", TspFileType::Man2d);
+}
+
+#[test]
+fn check_max_2d_file_type_works() {
+    check_file_type_works("max_2d", TspFileType::Max2d);
+}
+
 #[test]
 fn check_full_matrix_file_type_works() {
     check_file_type_works("full_matrix", TspFileType::FullMatrix);
@@ -37,12 +70,23 @@ fn check_lower_diag_row_file_type_works() {
     check_file_type_works("lower_diag_row", TspFileType::LowerDiagRow);
 }
 
+#[test]
+fn check_triangular_file_types_work() {
+    check_file_type_works("upper_row", TspFileType::UpperRow);
+    check_file_type_works("lower_row", TspFileType::LowerRow);
+    check_file_type_works("upper_diag_row", TspFileType::UpperDiagRow);
+    check_file_type_works("upper_col", TspFileType::UpperCol);
+    check_file_type_works("lower_col", TspFileType::LowerCol);
+    check_file_type_works("upper_diag_col", TspFileType::UpperDiagCol);
+    check_file_type_works("lower_diag_col", TspFileType::LowerDiagCol);
+}
+
 fn check_dimension_works(filename: &str, expected_dimension: usize) {
-    let file_content = std::fs::read_to_string(filename).expect("file doesn't exist");
-    let mut lines = file_content.lines();
+    let file = File::open(filename).expect("file doesn't exist");
+    let mut tokens = Tokens::new(BufReader::new(file));
 
-    let dimension = TspParser::check_dimension(&mut lines).expect("file couldn't be parsed");
-    assert_eq!(expected_dimension, dimension);
+    let (header, _) = TspHeader::parse(&mut tokens).expect("file couldn't be parsed");
+    assert_eq!(expected_dimension, header.dimension);
 }
 
 #[test]
@@ -65,16 +109,70 @@ fn lower_diag_row_works() {
     let tsp = TspParser::from_file("lower_diag_row").expect("Couldn't parse file");
     assert_eq!(
         vec![vec![0, 2, 3], vec![2, 0, 3], vec![3, 3, 0]],
-        tsp.get_edges()
+        tsp.get_distances().to_matrix()
+    );
+}
+
+fn check_triangular_fixture_works(filename: &str) {
+    let tsp = TspParser::from_file(filename).expect("Couldn't parse file");
+    assert_eq!(
+        vec![
+            vec![0, 1, 2, 3],
+            vec![1, 0, 4, 5],
+            vec![2, 4, 0, 6],
+            vec![3, 5, 6, 0]
+        ],
+        tsp.get_distances().to_matrix(),
+        "{}",
+        filename
     );
 }
 
+#[test]
+fn triangular_formats_work() {
+    check_triangular_fixture_works("upper_row");
+    check_triangular_fixture_works("lower_row");
+    check_triangular_fixture_works("upper_diag_row");
+    check_triangular_fixture_works("upper_col");
+    check_triangular_fixture_works("lower_col");
+    check_triangular_fixture_works("upper_diag_col");
+    check_triangular_fixture_works("lower_diag_col");
+}
+
+#[test]
+fn lower_diag_row_zero_weights_work() {
+    let tsp = TspParser::from_file("lower_diag_row_zeros").expect("Couldn't parse file");
+    assert_eq!(
+        vec![
+            vec![0, 0, 5, 0],
+            vec![0, 0, 5, 0],
+            vec![5, 5, 0, 5],
+            vec![0, 0, 5, 0]
+        ],
+        tsp.get_distances().to_matrix()
+    );
+}
+
+#[test]
+fn lower_diag_row_missing_weight() {
+    let tsp = TspParser::from_file("lower_diag_row_short");
+
+    assert!(matches!(
+        tsp,
+        Err(TspParsingError::MissingWeight {
+            row: 2,
+            column: 2,
+            line: 10
+        })
+    ));
+}
+
 #[test]
 fn full_matrix_works() {
     let tsp = TspParser::from_file("full_matrix").expect("Couldn't parse file");
     assert_eq!(
         vec![vec![9999, 2, 3], vec![2, 9999, 3], vec![3, 3, 9999]],
-        tsp.get_edges()
+        tsp.get_distances().to_matrix()
     );
 }
 
@@ -83,7 +181,7 @@ fn euc_2d_works() {
     let tsp = TspParser::from_file("euc_2d").expect("Couldn't parse file");
     assert_eq!(
         vec![vec![0, 10, 7], vec![10, 0, 7], vec![7, 7, 0]],
-        tsp.get_edges()
+        tsp.get_distances().to_matrix()
     );
 }
 
@@ -152,3 +250,406 @@ fn two_opt_works() {
 
     assert!(route_len > 0);
 }
+
+#[test]
+fn huge_euc_2d_is_coordinate_backed() {
+    let tsp = TspParser::from_file("test_files/d18512.tsp").expect("Couldn't parse file");
+
+    assert_eq!(18512, tsp.get_dimension());
+    assert!(matches!(
+        tsp.get_distances(),
+        Distances::Coordinates(_, Metric::Euc2d)
+    ));
+
+    let route = (0..tsp.get_dimension()).collect::<Vec<_>>();
+
+    assert!(tsp.get_route_len(&route).is_ok());
+}
+
+fn check_optimal_route_len(filename: &str, route: &[usize], expected_route_len: u64) {
+    let tsp = TspParser::from_file(filename).expect("Couldn't parse file");
+
+    let route_len = tsp.get_route_len(route).expect("route should be valid");
+
+    assert_eq!(expected_route_len, route_len);
+}
+
+// brute force over all routes starting in 0, only for the tiny fixtures
+fn brute_force_route_len(tsp: &Tsp) -> u64 {
+    fn visit(tsp: &Tsp, route: &mut Vec<usize>, best: &mut u64) {
+        if route.len() == tsp.get_dimension() {
+            *best = (*best).min(tsp.get_route_len(route).expect("route should be valid"));
+            return;
+        }
+
+        for vertex in 1..tsp.get_dimension() {
+            if !route.contains(&vertex) {
+                route.push(vertex);
+                visit(tsp, route, best);
+                route.pop();
+            }
+        }
+    }
+
+    let mut best = u64::MAX;
+
+    visit(tsp, &mut vec![0], &mut best);
+
+    best
+}
+
+#[test]
+fn geo_optimal_route_len() {
+    let route = [0, 13, 12, 11, 6, 5, 14, 4, 10, 8, 9, 15, 2, 1, 3, 7];
+
+    check_optimal_route_len("test_files/ulysses16.tsp", &route, 6859);
+}
+
+#[test]
+fn att_optimal_route_len() {
+    let route = [
+        0, 7, 37, 30, 43, 17, 6, 27, 5, 36, 18, 26, 16, 42, 29, 35, 45, 32, 19, 46, 20, 31, 38, 47,
+        4, 41, 23, 9, 44, 34, 3, 25, 1, 28, 33, 40, 15, 21, 2, 22, 13, 24, 12, 10, 11, 14, 39, 8,
+    ];
+
+    check_optimal_route_len("test_files/att48.tsp", &route, 10628);
+}
+
+#[test]
+fn ceil_2d_optimal_route_len() {
+    let tsp = TspParser::from_file("ceil_2d").expect("Couldn't parse file");
+
+    assert_eq!(27, brute_force_route_len(&tsp));
+    check_optimal_route_len("ceil_2d", &[0, 1, 2, 3, 4, 5], 27);
+}
+
+#[test]
+fn euc_3d_optimal_route_len() {
+    let tsp = TspParser::from_file("euc_3d").expect("Couldn't parse file");
+
+    assert_eq!(25, brute_force_route_len(&tsp));
+    check_optimal_route_len("euc_3d", &[0, 1, 5, 3, 4, 2], 25);
+}
+
+#[test]
+fn man_2d_optimal_route_len() {
+    let tsp = TspParser::from_file("man_2d").expect("Couldn't parse file");
+
+    assert_eq!(29, brute_force_route_len(&tsp));
+    check_optimal_route_len("man_2d", &[0, 1, 2, 3, 4, 5], 29);
+}
+
+#[test]
+fn max_2d_optimal_route_len() {
+    let tsp = TspParser::from_file("max_2d").expect("Couldn't parse file");
+
+    assert_eq!(20, brute_force_route_len(&tsp));
+    check_optimal_route_len("max_2d", &[0, 1, 2, 3, 4, 5], 20);
+}
+
+#[test]
+fn header_is_kept_on_tsp() {
+    let tsp = TspParser::from_file("test_files/att48.tsp").expect("Couldn't parse file");
+    let header = tsp.get_header();
+
+    assert_eq!(Some("att48"), header.name.as_deref());
+    assert_eq!(Some(ProblemType::Tsp), header.problem_type);
+    assert_eq!(
+        Some("48 capitals of the US (Padberg/Rinaldi)"),
+        header.comment.as_deref()
+    );
+    assert_eq!(48, header.dimension);
+    assert_eq!(Some("ATT"), header.edge_weight_type.as_deref());
+}
+
+#[test]
+fn dimension_after_edge_weight_type() {
+    let file_content = "NAME: reordered
+EDGE_WEIGHT_TYPE : EXPLICIT
+TYPE : TSP
+EDGE_WEIGHT_FORMAT : UPPER_ROW
+DISPLAY_DATA_TYPE : NO_DISPLAY
+DIMENSION : 3
+EDGE_WEIGHT_SECTION
+1 2
+3
+EOF";
+    let mut tokens = Tokens::new(file_content.as_bytes());
+
+    let (header, section) = TspHeader::parse(&mut tokens).expect("file couldn't be parsed");
+
+    assert_eq!("EDGE_WEIGHT_SECTION", section);
+    assert_eq!(3, header.dimension);
+    assert_eq!(
+        TspFileType::UpperRow,
+        header.file_type().expect("file type")
+    );
+}
+
+#[test]
+fn missing_file_keeps_io_error() {
+    let error = TspParser::from_file("test_files/missing.tsp").expect_err("file doesn't exist");
+
+    match error {
+        TspParsingError::FileDoesntExist(ref io_error) => {
+            assert_eq!(std::io::ErrorKind::NotFound, io_error.kind())
+        }
+        _ => panic!("expected FileDoesntExist"),
+    }
+
+    assert!(std::error::Error::source(&error).is_some());
+}
+
+#[test]
+fn malformed_weight_is_located() {
+    let file_content = "NAME: broken
+TYPE: ATSP
+DIMENSION: 2
+EDGE_WEIGHT_TYPE: EXPLICIT
+EDGE_WEIGHT_FORMAT: FULL_MATRIX
+EDGE_WEIGHT_SECTION
+ 0 1
+ 1 x
+EOF";
+    let mut tokens = Tokens::new(file_content.as_bytes());
+
+    TspHeader::parse(&mut tokens).expect("header is valid");
+    tokens.set_section("EDGE_WEIGHT_SECTION");
+
+    let error =
+        FullMatrixTspParser::parse::<_, u32>(&mut tokens, 2).expect_err("weight is not a number");
+
+    assert_eq!(
+        "weight is not a number: `x` at line 8, column 4 of EDGE_WEIGHT_SECTION",
+        error.to_string()
+    );
+}
+
+#[test]
+fn from_str_works() {
+    let file_content = std::fs::read_to_string("euc_2d").expect("file doesn't exist");
+
+    let tsp = TspParser::from_str(&file_content).expect("Couldn't parse file");
+    assert_eq!(
+        vec![vec![0, 10, 7], vec![10, 0, 7], vec![7, 7, 0]],
+        tsp.get_distances().to_matrix()
+    );
+
+    let tsp: Tsp = file_content.parse().expect("Couldn't parse file");
+    assert_eq!(Some("euc_2d"), tsp.get_header().name.as_deref());
+}
+
+#[test]
+fn from_reader_works() {
+    let file = File::open("test_files/ft70.atsp").expect("file doesn't exist");
+
+    let from_reader = TspParser::from_reader(BufReader::new(file)).expect("Couldn't parse file");
+    let from_file = TspParser::from_file("test_files/ft70.atsp").expect("Couldn't parse file");
+
+    assert_eq!(
+        from_file.get_distances().to_matrix(),
+        from_reader.get_distances().to_matrix()
+    );
+}
+
+#[test]
+fn display_data_is_kept() {
+    for (filename, first_position) in [
+        ("test_files/gr120.tsp", (8.0, 124.0)),
+        ("test_files/bays29.tsp", (1150.0, 1760.0)),
+        ("test_files/berlin52.tsp", (565.0, 575.0)),
+    ] {
+        let tsp = TspParser::from_file(filename).expect("Couldn't parse file");
+        let coordinates = tsp.coordinates().expect("file has node positions");
+
+        assert_eq!(tsp.get_dimension(), coordinates.len(), "{}", filename);
+        assert_eq!(first_position, coordinates[0], "{}", filename);
+    }
+
+    let tsp = TspParser::from_file("test_files/br17.atsp").expect("Couldn't parse file");
+
+    assert_eq!(None, tsp.coordinates());
+}
+
+#[test]
+fn display_data_before_weights() {
+    let file_content = "DIMENSION: 2
+EDGE_WEIGHT_TYPE: EXPLICIT
+EDGE_WEIGHT_FORMAT: FULL_MATRIX
+DISPLAY_DATA_SECTION
+1 0.0 1.0
+2 2.0 3.0
+EDGE_WEIGHT_SECTION
+0 5
+6 0
+EOF
+";
+
+    let tsp = TspParser::from_str(file_content).expect("Couldn't parse file");
+
+    assert_eq!(vec![vec![0, 5], vec![6, 0]], tsp.get_distances().to_matrix());
+    assert_eq!(Some(&[(0.0, 1.0), (2.0, 3.0)][..]), tsp.coordinates());
+}
+
+#[test]
+fn missing_weight_section() {
+    let file_content = "DIMENSION: 2
+EDGE_WEIGHT_TYPE: EXPLICIT
+EDGE_WEIGHT_FORMAT: FULL_MATRIX
+DISPLAY_DATA_SECTION
+1 0.0 1.0
+2 2.0 3.0
+EOF
+";
+
+    match TspParser::from_str(file_content) {
+        Err(TspParsingError::NotEnoughData { section, .. }) => {
+            assert_eq!("EDGE_WEIGHT_SECTION", section)
+        }
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+}
+
+#[test]
+fn invalid_fixed_edges_are_reported() {
+    let file_content = |fixed_edges: &str| {
+        format!(
+            "DIMENSION: 4
+EDGE_WEIGHT_TYPE: EUC_2D
+NODE_COORD_SECTION
+1 0 0
+2 1 0
+3 1 1
+4 0 1
+FIXED_EDGES_SECTION
+{}
+-1
+EOF
+",
+            fixed_edges
+        )
+    };
+
+    for (fixed_edges, expected) in [
+        ("1 2\n1 3\n1 4", FixedEdgesError::TooManyEdges(0)),
+        ("1 2\n2 3\n3 1", FixedEdgesError::ShortCycle(2)),
+    ] {
+        match TspParser::from_str(&file_content(fixed_edges)) {
+            Err(TspParsingError::InvalidFixedEdges(error)) => assert_eq!(expected, error),
+            result => panic!("unexpected result: {:?}", result.map(|_| ())),
+        }
+    }
+
+    // a fixed cycle through every vertex is a valid route
+    let tsp = TspParser::from_str(&file_content("1 2\n2 3\n3 4\n4 1")).unwrap();
+
+    assert!(tsp.check_route_valid(&[0, 1, 2, 3]).is_ok());
+}
+
+#[test]
+fn fractional_weights_work() {
+    let file_content = "DIMENSION: 3
+EDGE_WEIGHT_TYPE: EXPLICIT
+EDGE_WEIGHT_FORMAT: UPPER_ROW
+EDGE_WEIGHT_SECTION
+1.5 2.25
+0.5
+EOF
+";
+
+    let tsp = TspParser::<f64>::parse_str(file_content).expect("Couldn't parse file");
+
+    assert_eq!(2.25, tsp.get_edge(2, 0));
+    assert_eq!(4.25, tsp.get_route_len(&[0, 1, 2]).unwrap());
+
+    let route = TwoOpt::new(NearestNeighbour::new()).get_route(&tsp);
+
+    tsp.check_route_valid(&route).expect("Has to be valid route");
+    assert!(TspParser::from_str(file_content).is_err());
+}
+
+#[test]
+fn every_weight_type_keeps_rounding() {
+    let tsp = TspParser::from_file("test_files/ulysses16.tsp").expect("Couldn't parse file");
+    let route = Tour::from_file("test_files/ulysses16.opt.tour")
+        .expect("Couldn't parse tour")
+        .into_route();
+
+    let float_tsp: Tsp<f64> = TspParser::parse_file("test_files/ulysses16.tsp").unwrap();
+    let signed_tsp: Tsp<i64> = TspParser::parse_file("test_files/ulysses16.tsp").unwrap();
+    let wide_tsp: Tsp<u64> = TspParser::parse_file("test_files/ulysses16.tsp").unwrap();
+
+    assert_eq!(6859, tsp.get_route_len(&route).unwrap());
+    assert_eq!(6859.0, float_tsp.get_route_len(&route).unwrap());
+    assert_eq!(6859, signed_tsp.get_route_len(&route).unwrap());
+    assert_eq!(6859, wide_tsp.get_route_len(&route).unwrap());
+}
+
+#[test]
+fn wide_weights_work() {
+    let file_content = "DIMENSION: 2
+EDGE_WEIGHT_TYPE: EXPLICIT
+EDGE_WEIGHT_FORMAT: FULL_MATRIX
+EDGE_WEIGHT_SECTION
+0 10000000000
+-5 0
+EOF
+";
+
+    assert!(TspParser::<u64>::parse_str(file_content).is_err());
+
+    let tsp = file_content.parse::<Tsp<i64>>().expect("Couldn't parse file");
+
+    assert_eq!(9999999995, tsp.get_route_len(&[0, 1]).unwrap());
+}
+
+#[cfg(any(feature = "gzip", feature = "bzip2"))]
+fn check_compressed_file_works(filename: &str, compressed_filename: &str) {
+    let plain = TspParser::from_file(filename).expect("Couldn't parse file");
+    let compressed = TspParser::from_file(compressed_filename).expect("Couldn't parse file");
+
+    assert_eq!(
+        plain.get_distances().to_matrix(),
+        compressed.get_distances().to_matrix()
+    );
+    assert_eq!(plain.get_header().name, compressed.get_header().name);
+    assert_eq!(plain.coordinates(), compressed.coordinates());
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn gzip_files_work() {
+    check_compressed_file_works("test_files/gr17.tsp", "test_files/gr17.tsp.gz");
+    check_compressed_file_works("test_files/ulysses16.tsp", "test_files/ulysses16.tsp.gz");
+}
+
+#[cfg(feature = "bzip2")]
+#[test]
+fn bzip2_files_work() {
+    check_compressed_file_works("test_files/br17.atsp", "test_files/br17.atsp.bz2");
+}
+
+#[cfg(not(feature = "gzip"))]
+#[test]
+fn gzip_file_needs_feature() {
+    let result = TspParser::<u32>::parse_file("test_files/gr17.tsp.gz");
+
+    assert!(matches!(
+        result,
+        Err(TspParsingError::CompressionNotSupported(Compression::Gzip))
+    ));
+}
+
+#[test]
+fn full_matrix_symmetry_is_detected() {
+    for (filename, tsp_type) in [
+        ("full_matrix", TspType::Symmetric),
+        ("test_files/bays29.tsp", TspType::Symmetric),
+        ("test_files/br17.atsp", TspType::Asymmetric),
+        ("test_files/ft70.atsp", TspType::Asymmetric),
+    ] {
+        let tsp = TspParser::<u32>::parse_file(filename).expect("Couldn't parse file");
+
+        assert_eq!(tsp_type, tsp.get_tsp_type(), "{}", filename);
+    }
+}
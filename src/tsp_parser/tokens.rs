@@ -0,0 +1,280 @@
+use std::io::BufRead;
+use std::str::FromStr;
+
+use crate::tsp_parser::{TokenContext, TspParsingError};
+
+pub struct Token<'a> {
+    pub text: &'a str,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Whitespace separated tokens of a TSPLIB file which remember where they
+/// come from, so every parsing error can point at the offending line.
+///
+/// Only the current line is kept in memory, so files of any size can be
+/// streamed from a reader.
+pub struct Tokens<R> {
+    reader: R,
+    line: String,
+    line_number: usize,
+    offset: usize,
+    section: String,
+}
+
+impl<R> Tokens<R>
+where
+    R: BufRead,
+{
+    pub fn new(reader: R) -> Tokens<R> {
+        Tokens {
+            reader,
+            line: String::new(),
+            line_number: 0,
+            offset: 0,
+            section: String::from("header"),
+        }
+    }
+
+    /// Whole next line, used for the line oriented specification part.
+    pub fn next_line(&mut self) -> Result<Option<&str>, TspParsingError> {
+        if !self.read_line()? {
+            return Ok(None);
+        }
+
+        self.offset = self.line.len();
+
+        Ok(Some(&self.line))
+    }
+
+    fn read_line(&mut self) -> Result<bool, TspParsingError> {
+        self.line.clear();
+        self.offset = 0;
+
+        let read = self
+            .reader
+            .read_line(&mut self.line)
+            .map_err(TspParsingError::ReadFailed)?;
+
+        if read == 0 {
+            return Ok(false);
+        }
+
+        let line_len = self.line.trim_end_matches(['\n', '\r']).len();
+        self.line.truncate(line_len);
+        self.line_number += 1;
+
+        Ok(true)
+    }
+
+    // moves offset to the start of the next token, false at the end of input
+    fn skip_whitespace(&mut self) -> Result<bool, TspParsingError> {
+        loop {
+            let rest = &self.line[self.offset..];
+            let trimmed = rest.trim_start();
+
+            if !trimmed.is_empty() {
+                self.offset += rest.len() - trimmed.len();
+
+                return Ok(true);
+            }
+
+            if !self.read_line()? {
+                return Ok(false);
+            }
+        }
+    }
+
+    fn current_token(&self) -> Token<'_> {
+        let rest = &self.line[self.offset..];
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+
+        Token {
+            text: &rest[..end],
+            line: self.line_number,
+            column: self.offset + 1,
+        }
+    }
+
+    pub fn peek_token(&mut self) -> Result<Option<Token<'_>>, TspParsingError> {
+        if !self.skip_whitespace()? {
+            return Ok(None);
+        }
+
+        Ok(Some(self.current_token()))
+    }
+
+    /// Owned context of the next token, handy when it ends up in an error.
+    pub fn peek_context(&mut self) -> Result<Option<TokenContext>, TspParsingError> {
+        if !self.skip_whitespace()? {
+            return Ok(None);
+        }
+
+        Ok(Some(self.context(&self.current_token())))
+    }
+
+    pub fn next_token(&mut self) -> Result<Option<Token<'_>>, TspParsingError> {
+        if !self.skip_whitespace()? {
+            return Ok(None);
+        }
+
+        let start = self.offset;
+        self.offset += self.current_token().text.len();
+
+        Ok(Some(Token {
+            text: &self.line[start..self.offset],
+            line: self.line_number,
+            column: start + 1,
+        }))
+    }
+
+    /// Next number of the current section, `None` once the section ends with
+    /// EOF, another section keyword or the end of input.
+    pub fn next_number<T, E>(&mut self, error: E) -> Result<Option<T>, TspParsingError>
+    where
+        T: FromStr,
+        E: FnOnce(TokenContext) -> TspParsingError,
+    {
+        if !self.skip_whitespace()? {
+            return Ok(None);
+        }
+
+        let token = self.current_token();
+
+        if is_keyword(token.text) {
+            return Ok(None);
+        }
+
+        let number = token
+            .text
+            .parse()
+            .map_err(|_| error(self.context(&token)))?;
+
+        self.offset += token.text.len();
+
+        Ok(Some(number))
+    }
+
+    /// Like `next_number`, but running out of data is an error.
+    pub fn expect_number<T, E>(&mut self, error: E) -> Result<T, TspParsingError>
+    where
+        T: FromStr,
+        E: FnOnce(TokenContext) -> TspParsingError,
+    {
+        self.next_number(error)?
+            .ok_or_else(|| TspParsingError::NotEnoughData {
+                section: self.section.clone(),
+                line: self.line_number,
+            })
+    }
+
+    /// Skips what's left of the current section and returns the next
+    /// section keyword, `None` at the end of input.
+    pub fn next_section(&mut self) -> Result<Option<String>, TspParsingError> {
+        while let Some(token) = self.next_token()? {
+            if is_keyword(token.text) {
+                return Ok(Some(token.text.to_string()));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn context(&self, token: &Token) -> TokenContext {
+        TokenContext {
+            line: token.line,
+            column: token.column,
+            token: token.text.to_string(),
+            section: self.section.clone(),
+        }
+    }
+
+    pub fn section(&self) -> &str {
+        &self.section
+    }
+
+    pub fn set_section(&mut self, section: &str) {
+        self.section = section.to_string();
+    }
+
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+}
+
+pub fn is_keyword(text: &str) -> bool {
+    text == "EOF" || text.ends_with("_SECTION")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_remember_position() {
+        let mut tokens = Tokens::new("1 2\r\n\r\n   33 4".as_bytes());
+        let mut positions = Vec::new();
+
+        while let Some(token) = tokens.next_token().expect("input is valid") {
+            positions.push((token.text.to_string(), token.line, token.column));
+        }
+
+        assert_eq!(
+            vec![
+                ("1".to_string(), 1, 1),
+                ("2".to_string(), 1, 3),
+                ("33".to_string(), 3, 4),
+                ("4".to_string(), 3, 7)
+            ],
+            positions
+        );
+    }
+
+    #[test]
+    fn numbers_stop_at_keyword() {
+        let mut tokens = Tokens::new("1 2\nEOF".as_bytes());
+
+        let mut number = || tokens.next_number::<u32, _>(TspParsingError::WeightNotANumber);
+
+        assert_eq!(Some(1), number().expect("is a number"));
+        assert_eq!(Some(2), number().expect("is a number"));
+        assert_eq!(None, number().expect("section end"));
+
+        let keyword = tokens.next_token().expect("input is valid");
+
+        assert_eq!(Some("EOF"), keyword.map(|token| token.text));
+    }
+
+    #[test]
+    fn wrong_number_has_context() {
+        let mut tokens = Tokens::new("1\n 2x".as_bytes());
+        tokens.set_section("EDGE_WEIGHT_SECTION");
+
+        tokens.next_token().expect("input is valid");
+
+        let error = tokens.next_number::<u32, _>(TspParsingError::WeightNotANumber);
+
+        match error {
+            Err(TspParsingError::WeightNotANumber(context)) => assert_eq!(
+                TokenContext {
+                    line: 2,
+                    column: 2,
+                    token: "2x".to_string(),
+                    section: "EDGE_WEIGHT_SECTION".to_string(),
+                },
+                context
+            ),
+            _ => panic!("expected WeightNotANumber"),
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_is_read_error() {
+        let mut tokens = Tokens::new(&[b'1', b' ', 0xff, b'\n'][..]);
+
+        assert!(matches!(
+            tokens.next_token(),
+            Err(TspParsingError::ReadFailed(_))
+        ));
+    }
+}
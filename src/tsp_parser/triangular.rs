@@ -0,0 +1,235 @@
+use std::io::BufRead;
+
+use crate::distances::Distances;
+use crate::matrix::PackedMatrix;
+use crate::tsp_parser::tokens::{is_keyword, Tokens};
+use crate::tsp_parser::{TspFileType, TspParsingError};
+use crate::weight::Weight;
+
+/// Layouts of EXPLICIT symmetric matrices that store only one triangle.
+///
+/// Column formats list the same pairs as the opposite row formats, e.g.
+/// UPPER_COL walks the pairs in the same order as LOWER_ROW, which is why
+/// they are reduced to the row variants when generating positions.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TriangularFormat {
+    UpperRow,
+    LowerRow,
+    UpperDiagRow,
+    LowerDiagRow,
+    UpperCol,
+    LowerCol,
+    UpperDiagCol,
+    LowerDiagCol,
+}
+
+impl TriangularFormat {
+    pub fn from_file_type(file_type: TspFileType) -> Option<TriangularFormat> {
+        match file_type {
+            TspFileType::UpperRow => Some(TriangularFormat::UpperRow),
+            TspFileType::LowerRow => Some(TriangularFormat::LowerRow),
+            TspFileType::UpperDiagRow => Some(TriangularFormat::UpperDiagRow),
+            TspFileType::LowerDiagRow => Some(TriangularFormat::LowerDiagRow),
+            TspFileType::UpperCol => Some(TriangularFormat::UpperCol),
+            TspFileType::LowerCol => Some(TriangularFormat::LowerCol),
+            TspFileType::UpperDiagCol => Some(TriangularFormat::UpperDiagCol),
+            TspFileType::LowerDiagCol => Some(TriangularFormat::LowerDiagCol),
+            _ => None,
+        }
+    }
+
+    fn as_row_format(self) -> TriangularFormat {
+        match self {
+            TriangularFormat::UpperCol => TriangularFormat::LowerRow,
+            TriangularFormat::LowerCol => TriangularFormat::UpperRow,
+            TriangularFormat::UpperDiagCol => TriangularFormat::LowerDiagRow,
+            TriangularFormat::LowerDiagCol => TriangularFormat::UpperDiagRow,
+            row_format => row_format,
+        }
+    }
+
+    fn row_positions(self, row: usize, dimension: usize) -> std::ops::Range<usize> {
+        match self.as_row_format() {
+            TriangularFormat::UpperRow => row + 1..dimension,
+            TriangularFormat::LowerRow => 0..row,
+            TriangularFormat::UpperDiagRow => row..dimension,
+            _ => 0..row + 1,
+        }
+    }
+
+    /// Matrix positions in the order their weights appear in the file.
+    pub fn positions(self, dimension: usize) -> impl Iterator<Item = (usize, usize)> {
+        (0..dimension).flat_map(move |row| {
+            self.row_positions(row, dimension)
+                .map(move |column| (row, column))
+        })
+    }
+}
+
+pub struct TriangularTspParser;
+
+impl TriangularTspParser {
+    pub fn parse<R: BufRead, W: Weight>(
+        tokens: &mut Tokens<R>,
+        dimension: usize,
+        format: TriangularFormat,
+    ) -> Result<Distances<W>, TspParsingError> {
+        let mut edges = PackedMatrix::new(dimension);
+        let mut weight_count = 0;
+
+        for (row, column) in format.positions(dimension) {
+            let weight = tokens
+                .next_number(TspParsingError::WeightNotANumber)?
+                .ok_or(TspParsingError::MissingWeight {
+                    row,
+                    column,
+                    line: tokens.line_number(),
+                })?;
+
+            edges.set(row, column, weight);
+
+            weight_count += 1;
+        }
+
+        let extra_weight = tokens
+            .peek_context()?
+            .filter(|context| !is_keyword(&context.token));
+
+        if let Some(context) = extra_weight {
+            return Err(TspParsingError::TooManyWeights {
+                expected: weight_count,
+                context,
+            });
+        }
+
+        Ok(Distances::Packed(edges))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_format(data: &str, format: TriangularFormat) {
+        let mut tokens = Tokens::new(data.as_bytes());
+
+        let edges: Distances =
+            TriangularTspParser::parse(&mut tokens, 4, format).expect("error while parsing data");
+
+        assert_eq!(
+            vec![
+                vec![0, 1, 2, 3],
+                vec![1, 0, 4, 5],
+                vec![2, 4, 0, 6],
+                vec![3, 5, 6, 0]
+            ],
+            edges.to_matrix()
+        );
+    }
+
+    #[test]
+    fn upper_row_works() {
+        check_format("1 2 3\n4 5\n6", TriangularFormat::UpperRow);
+    }
+
+    #[test]
+    fn lower_row_works() {
+        check_format("1\n2 4\n3 5 6", TriangularFormat::LowerRow);
+    }
+
+    #[test]
+    fn upper_diag_row_works() {
+        check_format("0 1 2 3\n0 4 5\n0 6\n0", TriangularFormat::UpperDiagRow);
+    }
+
+    #[test]
+    fn lower_diag_row_works() {
+        check_format("0\n1 0\n2 4 0\n3 5 6 0", TriangularFormat::LowerDiagRow);
+    }
+
+    #[test]
+    fn upper_col_works() {
+        check_format("1\n2 4\n3 5 6", TriangularFormat::UpperCol);
+    }
+
+    #[test]
+    fn lower_col_works() {
+        check_format("1 2 3\n4 5\n6", TriangularFormat::LowerCol);
+    }
+
+    #[test]
+    fn upper_diag_col_works() {
+        check_format("0\n1 0\n2 4 0\n3 5 6 0", TriangularFormat::UpperDiagCol);
+    }
+
+    #[test]
+    fn lower_diag_col_works() {
+        check_format("0 1 2 3\n0 4 5\n0 6\n0", TriangularFormat::LowerDiagCol);
+    }
+
+    #[test]
+    fn weights_can_span_lines() {
+        check_format("1 2\n3 4 5 6", TriangularFormat::UpperRow);
+    }
+
+    #[test]
+    fn not_enough_weights() {
+        let mut tokens = Tokens::new("1 2 3\n4 5".as_bytes());
+
+        let edges: Result<Distances, _> =
+            TriangularTspParser::parse(&mut tokens, 4, TriangularFormat::UpperRow);
+
+        assert!(matches!(
+            edges,
+            Err(TspParsingError::MissingWeight {
+                row: 2,
+                column: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn too_many_weights() {
+        let mut tokens = Tokens::new("1 2 3\n4 5\n6 7\nEOF".as_bytes());
+
+        let edges: Result<Distances, _> =
+            TriangularTspParser::parse(&mut tokens, 4, TriangularFormat::UpperRow);
+
+        assert!(matches!(
+            edges,
+            Err(TspParsingError::TooManyWeights { expected: 6, ref context }) if context.token == "7"
+        ));
+    }
+
+    #[test]
+    fn section_end_is_not_a_weight() {
+        let mut tokens = Tokens::new("1 2 3\n4 5\nEOF".as_bytes());
+
+        let edges: Result<Distances, _> =
+            TriangularTspParser::parse(&mut tokens, 4, TriangularFormat::UpperRow);
+
+        assert!(matches!(
+            edges,
+            Err(TspParsingError::MissingWeight {
+                row: 2,
+                column: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn zero_off_diagonal_weights() {
+        let mut tokens = Tokens::new("0\n0 0\n2 2 0\nEOF".as_bytes());
+
+        let edges: Distances =
+            TriangularTspParser::parse(&mut tokens, 3, TriangularFormat::LowerDiagRow)
+                .expect("error while parsing data");
+
+        assert_eq!(
+            vec![vec![0, 0, 2], vec![0, 0, 2], vec![2, 2, 0]],
+            edges.to_matrix()
+        );
+    }
+}
@@ -12,6 +12,16 @@ mod tests {
             let filename = filename.unwrap().file_name();
             let str_filename = String::from_utf8_lossy(filename.as_bytes());
 
+            if str_filename.ends_with(".tour") || str_filename.ends_with(".cache") {
+                continue;
+            }
+
+            if (str_filename.ends_with(".gz") && !cfg!(feature = "gzip"))
+                || (str_filename.ends_with(".bz2") && !cfg!(feature = "bzip2"))
+            {
+                continue;
+            }
+
             let tsp = TspParser::from_file(&format!("test_files/{}", str_filename));
 
             assert!(tsp.is_ok(), "{}", str_filename);
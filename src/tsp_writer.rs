@@ -0,0 +1,499 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::cvrp::Cvrp;
+use crate::distances::{Distances, Metric};
+use crate::sop::Sop;
+use crate::tsp::{Tsp, TspType};
+use crate::tsp_analysis::is_symmetric;
+use crate::tsp_parser::triangular::TriangularFormat;
+use crate::tsp_parser::{ProblemType, TspFileType};
+use crate::weight::Weight;
+
+pub struct TspWriter;
+
+impl TspWriter {
+    pub fn to_file<T: Weight>(tsp: &Tsp<T>, filename: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(filename)?);
+
+        TspWriter::to_writer(tsp, &mut writer)?;
+
+        writer.flush()
+    }
+
+    pub fn to_string<T: Weight>(tsp: &Tsp<T>) -> String {
+        let mut content = Vec::new();
+
+        TspWriter::to_writer(tsp, &mut content).expect("writing into memory can't fail");
+
+        String::from_utf8(content).expect("header strings are valid utf-8")
+    }
+
+    /// Writes coordinates when they are known, otherwise a LOWER_DIAG_ROW
+    /// matrix for symmetric and a FULL_MATRIX for asymmetric instances.
+    pub fn to_writer<T: Weight, W: Write>(tsp: &Tsp<T>, writer: W) -> io::Result<()> {
+        TspWriter::to_writer_as(tsp, writer, TspWriter::default_file_type(tsp))
+    }
+
+    /// Every instance can be written as any EXPLICIT format (triangular ones
+    /// only if the matrix is symmetric), coordinates only with their own metric.
+    ///
+    /// The instance is always written as TSP or ATSP, CVRP and SOP data is
+    /// only written by `cvrp_to_writer` and `sop_to_writer`.
+    pub fn to_writer_as<T: Weight, W: Write>(
+        tsp: &Tsp<T>,
+        mut writer: W,
+        file_type: TspFileType,
+    ) -> io::Result<()> {
+        TspWriter::check_file_type(tsp, file_type)?;
+
+        let problem_type = match tsp.get_tsp_type() {
+            TspType::Symmetric => ProblemType::Tsp,
+            TspType::Asymmetric => ProblemType::Atsp,
+        };
+
+        TspWriter::write_header(tsp, &mut writer, file_type, problem_type, None)?;
+        TspWriter::write_distances(tsp, &mut writer, file_type)?;
+        TspWriter::write_fixed_edges(tsp, &mut writer)?;
+
+        writeln!(writer, "EOF")
+    }
+
+    pub fn cvrp_to_string<T: Weight>(cvrp: &Cvrp<T>) -> String {
+        let mut content = Vec::new();
+
+        TspWriter::cvrp_to_writer(cvrp, &mut content).expect("writing into memory can't fail");
+
+        String::from_utf8(content).expect("header strings are valid utf-8")
+    }
+
+    /// Writes distances like `to_writer`, followed by DEMAND_SECTION and
+    /// DEPOT_SECTION.
+    pub fn cvrp_to_writer<T: Weight, W: Write>(cvrp: &Cvrp<T>, mut writer: W) -> io::Result<()> {
+        let tsp = cvrp.get_tsp();
+        let file_type = TspWriter::default_file_type(tsp);
+        let capacity = Some(cvrp.get_capacity());
+
+        TspWriter::write_header(tsp, &mut writer, file_type, ProblemType::Cvrp, capacity)?;
+        TspWriter::write_distances(tsp, &mut writer, file_type)?;
+        TspWriter::write_fixed_edges(tsp, &mut writer)?;
+
+        writeln!(writer, "DEMAND_SECTION")?;
+
+        for (i, demand) in cvrp.get_demands().iter().enumerate() {
+            writeln!(writer, "{} {}", i + 1, demand)?;
+        }
+
+        writeln!(writer, "DEPOT_SECTION")?;
+        writeln!(writer, "{}", cvrp.get_depot() + 1)?;
+        writeln!(writer, "-1")?;
+
+        writeln!(writer, "EOF")
+    }
+
+    pub fn sop_to_string<T: Weight>(sop: &Sop<T>) -> String {
+        let mut content = Vec::new();
+
+        TspWriter::sop_to_writer(sop, &mut content).expect("writing into memory can't fail");
+
+        String::from_utf8(content).expect("header strings are valid utf-8")
+    }
+
+    /// Writes a FULL_MATRIX preceded by the dimension, with -1 at row i,
+    /// column j when j has to be visited before i.
+    pub fn sop_to_writer<T: Weight, W: Write>(sop: &Sop<T>, mut writer: W) -> io::Result<()> {
+        let tsp = sop.get_tsp();
+        let dimension = tsp.get_dimension();
+        let file_type = TspFileType::FullMatrix;
+
+        TspWriter::write_header(tsp, &mut writer, file_type, ProblemType::Sop, None)?;
+
+        writeln!(writer, "EDGE_WEIGHT_SECTION")?;
+        writeln!(writer, "{}", dimension)?;
+
+        for row in 0..dimension {
+            let predecessors = sop.get_predecessors(row);
+
+            for column in 0..dimension {
+                if column != 0 {
+                    write!(writer, " ")?;
+                }
+
+                if predecessors.contains(&column) {
+                    write!(writer, "-1")?;
+                } else {
+                    write!(writer, "{}", tsp.get_edge(row, column))?;
+                }
+            }
+
+            writeln!(writer)?;
+        }
+
+        TspWriter::write_display_data(tsp, &mut writer)?;
+        TspWriter::write_fixed_edges(tsp, &mut writer)?;
+
+        writeln!(writer, "EOF")
+    }
+
+    fn write_distances<T: Weight, W: Write>(
+        tsp: &Tsp<T>,
+        writer: &mut W,
+        file_type: TspFileType,
+    ) -> io::Result<()> {
+        match (tsp.get_distances(), file_type.edge_weight_format()) {
+            (Distances::Coordinates(coords, _), None) => {
+                writeln!(writer, "NODE_COORD_SECTION")?;
+
+                for (i, (x, y)) in coords.iter().enumerate() {
+                    writeln!(writer, "{} {} {}", i + 1, x, y)?;
+                }
+            }
+            (Distances::Coordinates3d(coords), None) => {
+                writeln!(writer, "NODE_COORD_SECTION")?;
+
+                for (i, (x, y, z)) in coords.iter().enumerate() {
+                    writeln!(writer, "{} {} {} {}", i + 1, x, y, z)?;
+                }
+            }
+            _ => {
+                TspWriter::write_edge_weights(tsp, writer, file_type)?;
+                TspWriter::write_display_data(tsp, writer)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_display_data<T: Weight, W: Write>(tsp: &Tsp<T>, writer: &mut W) -> io::Result<()> {
+        if let Some(coords) = tsp.coordinates() {
+            writeln!(writer, "DISPLAY_DATA_SECTION")?;
+
+            for (i, (x, y)) in coords.iter().enumerate() {
+                writeln!(writer, "{} {} {}", i + 1, x, y)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_fixed_edges<T: Weight, W: Write>(tsp: &Tsp<T>, writer: &mut W) -> io::Result<()> {
+        if !tsp.get_fixed_edges().is_empty() {
+            writeln!(writer, "FIXED_EDGES_SECTION")?;
+
+            for (from, to) in tsp.get_fixed_edges() {
+                writeln!(writer, "{} {}", from + 1, to + 1)?;
+            }
+
+            writeln!(writer, "-1")?;
+        }
+
+        Ok(())
+    }
+
+    fn default_file_type<T: Weight>(tsp: &Tsp<T>) -> TspFileType {
+        match tsp.get_distances() {
+            Distances::Coordinates(_, metric) => metric_file_type(*metric),
+            Distances::Coordinates3d(_) => TspFileType::Euc3d,
+            Distances::Matrix(_) | Distances::Packed(_) | Distances::Graph(_) => {
+                match tsp.get_tsp_type() {
+                    TspType::Symmetric => TspFileType::LowerDiagRow,
+                    TspType::Asymmetric => TspFileType::FullMatrix,
+                }
+            }
+        }
+    }
+
+    fn check_file_type<T: Weight>(tsp: &Tsp<T>, file_type: TspFileType) -> io::Result<()> {
+        if file_type.edge_weight_format().is_none() {
+            let is_own_metric = match tsp.get_distances() {
+                Distances::Matrix(_) | Distances::Packed(_) | Distances::Graph(_) => false,
+                _ => TspWriter::default_file_type(tsp) == file_type,
+            };
+
+            if !is_own_metric {
+                return Err(invalid_input("coordinates in this metric are unknown"));
+            }
+        }
+
+        if TriangularFormat::from_file_type(file_type).is_some()
+            && !is_symmetric(tsp.get_distances(), tsp.get_dimension())
+        {
+            return Err(invalid_input("asymmetric matrix can't be triangular"));
+        }
+
+        Ok(())
+    }
+
+    fn write_header<T: Weight, W: Write>(
+        tsp: &Tsp<T>,
+        writer: &mut W,
+        file_type: TspFileType,
+        problem_type: ProblemType,
+        capacity: Option<u32>,
+    ) -> io::Result<()> {
+        let header = tsp.get_header();
+
+        if let Some(name) = &header.name {
+            writeln!(writer, "NAME: {}", name)?;
+        }
+
+        writeln!(writer, "TYPE: {}", problem_type.keyword())?;
+
+        for comment in header.comment.iter().flat_map(|comment| comment.lines()) {
+            writeln!(writer, "COMMENT: {}", comment)?;
+        }
+
+        writeln!(writer, "DIMENSION: {}", tsp.get_dimension())?;
+
+        if let Some(capacity) = capacity {
+            writeln!(writer, "CAPACITY: {}", capacity)?;
+        }
+
+        writeln!(writer, "EDGE_WEIGHT_TYPE: {}", file_type.edge_weight_type())?;
+
+        if let Some(edge_weight_format) = file_type.edge_weight_format() {
+            writeln!(writer, "EDGE_WEIGHT_FORMAT: {}", edge_weight_format)?;
+
+            if tsp.coordinates().is_some() {
+                writeln!(writer, "DISPLAY_DATA_TYPE: TWOD_DISPLAY")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // every matrix row (or column for the *_COL formats) goes on its own line
+    fn write_edge_weights<T: Weight, W: Write>(
+        tsp: &Tsp<T>,
+        writer: &mut W,
+        file_type: TspFileType,
+    ) -> io::Result<()> {
+        let dimension = tsp.get_dimension();
+
+        writeln!(writer, "EDGE_WEIGHT_SECTION")?;
+
+        let positions: Box<dyn Iterator<Item = (usize, usize)>> =
+            match TriangularFormat::from_file_type(file_type) {
+                Some(format) => Box::new(format.positions(dimension)),
+                None => {
+                    Box::new((0..dimension).flat_map(move |i| (0..dimension).map(move |j| (i, j))))
+                }
+            };
+
+        let mut curr_row = None;
+
+        for (row, column) in positions {
+            match curr_row {
+                Some(curr_row) if curr_row == row => write!(writer, " ")?,
+                Some(_) => writeln!(writer)?,
+                None => {}
+            }
+
+            write!(writer, "{}", tsp.get_edge(row, column))?;
+            curr_row = Some(row);
+        }
+
+        if curr_row.is_some() {
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn metric_file_type(metric: Metric) -> TspFileType {
+    match metric {
+        Metric::Euc2d => TspFileType::Euc2d,
+        Metric::Ceil2d => TspFileType::Ceil2d,
+        Metric::Man2d => TspFileType::Man2d,
+        Metric::Max2d => TspFileType::Max2d,
+        Metric::Geo => TspFileType::Geo,
+        Metric::Att => TspFileType::Att,
+    }
+}
+
+fn invalid_input(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TspParser, TspParsingError};
+
+    fn check_round_trip(filename: &str) {
+        let tsp = TspParser::from_file(filename).expect("Couldn't parse file");
+
+        let written = TspWriter::to_string(&tsp);
+        let parsed = TspParser::from_str(&written).expect("Couldn't parse written file");
+
+        assert_eq!(
+            tsp.get_distances().to_matrix(),
+            parsed.get_distances().to_matrix(),
+            "{}",
+            filename
+        );
+        assert_eq!(tsp.get_header().name, parsed.get_header().name);
+        assert_eq!(tsp.get_header().comment, parsed.get_header().comment);
+        assert_eq!(tsp.coordinates(), parsed.coordinates());
+        assert_eq!(tsp.get_fixed_edges(), parsed.get_fixed_edges());
+        assert_eq!(
+            TspWriter::default_file_type(&tsp),
+            parsed
+                .get_header()
+                .file_type()
+                .expect("file type is written")
+        );
+    }
+
+    #[test]
+    fn round_trip_default_formats() {
+        for filename in [
+            "euc_2d",
+            "euc_3d",
+            "ceil_2d",
+            "man_2d",
+            "max_2d",
+            "test_files/ulysses16.tsp",
+            "test_files/att48.tsp",
+            "full_matrix",
+            "lower_diag_row",
+            "upper_row",
+            "lower_diag_col",
+            "test_files/gr17.tsp",
+            "test_files/br17.atsp",
+            "test_files/bays29.tsp",
+            "fixed_edges",
+            "cvrp",
+            "sop",
+        ] {
+            check_round_trip(filename);
+        }
+    }
+
+    #[test]
+    fn plain_tsp_drops_problem_data() {
+        let cvrp = TspParser::from_file("cvrp").expect("Couldn't parse file");
+        let sop = TspParser::from_file("sop").expect("Couldn't parse file");
+
+        let written_cvrp = TspWriter::to_string(&cvrp);
+        let written_sop = TspWriter::to_string(&sop);
+
+        assert!(written_cvrp.contains("TYPE: TSP\n"));
+        assert!(!written_cvrp.contains("CAPACITY"));
+        assert!(written_sop.contains("TYPE: ATSP\n"));
+        assert!(!written_sop.contains("-1"));
+        assert!(matches!(
+            written_cvrp.parse::<Cvrp>(),
+            Err(TspParsingError::NoCapacity)
+        ));
+    }
+
+    #[test]
+    fn round_trip_cvrp() {
+        let cvrp = Cvrp::from_file("cvrp").expect("Couldn't parse file");
+
+        let written = TspWriter::cvrp_to_string(&cvrp);
+        let parsed = written
+            .parse::<Cvrp>()
+            .expect("Couldn't parse written file");
+
+        assert_eq!(
+            cvrp.get_tsp().get_distances().to_matrix(),
+            parsed.get_tsp().get_distances().to_matrix()
+        );
+        assert_eq!(cvrp.get_capacity(), parsed.get_capacity());
+        assert_eq!(cvrp.get_demands(), parsed.get_demands());
+        assert_eq!(cvrp.get_depot(), parsed.get_depot());
+        assert_eq!(cvrp.get_tsp().coordinates(), parsed.get_tsp().coordinates());
+    }
+
+    #[test]
+    fn round_trip_sop() {
+        let sop = Sop::from_file("sop").expect("Couldn't parse file");
+
+        let written = TspWriter::sop_to_string(&sop);
+        let parsed = written.parse::<Sop>().expect("Couldn't parse written file");
+
+        assert_eq!(
+            sop.get_tsp().get_distances().to_matrix(),
+            parsed.get_tsp().get_distances().to_matrix()
+        );
+        assert_eq!(sop.get_precedences(), parsed.get_precedences());
+        assert_eq!(
+            Some(ProblemType::Sop),
+            parsed.get_tsp().get_header().problem_type
+        );
+    }
+
+    #[test]
+    fn round_trip_explicit_formats() {
+        let tsp = TspParser::from_file("test_files/berlin52.tsp").expect("Couldn't parse file");
+
+        for file_type in [
+            TspFileType::FullMatrix,
+            TspFileType::UpperRow,
+            TspFileType::LowerRow,
+            TspFileType::UpperDiagRow,
+            TspFileType::LowerDiagRow,
+            TspFileType::UpperCol,
+            TspFileType::LowerCol,
+            TspFileType::UpperDiagCol,
+            TspFileType::LowerDiagCol,
+        ] {
+            let mut written = Vec::new();
+
+            TspWriter::to_writer_as(&tsp, &mut written, file_type).expect("format is explicit");
+
+            let written = String::from_utf8(written).expect("output is utf-8");
+            let parsed = TspParser::from_str(&written).expect("Couldn't parse written file");
+
+            assert_eq!(
+                tsp.get_distances().to_matrix(),
+                parsed.get_distances().to_matrix(),
+                "{:?}",
+                file_type
+            );
+            assert_eq!(tsp.coordinates(), parsed.coordinates());
+        }
+    }
+
+    #[test]
+    fn asymmetric_matrix_isnt_triangular() {
+        let tsp = TspParser::from_file("test_files/br17.atsp").expect("Couldn't parse file");
+
+        let result = TspWriter::to_writer_as(&tsp, Vec::new(), TspFileType::UpperRow);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn matrix_has_no_coordinates() {
+        let tsp = TspParser::from_file("lower_diag_row").expect("Couldn't parse file");
+
+        let result = TspWriter::to_writer_as(&tsp, Vec::new(), TspFileType::Euc2d);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn writer_output() {
+        let tsp = TspParser::from_file("lower_diag_row").expect("Couldn't parse file");
+
+        assert_eq!(
+            "NAME: lower_diag_row
+TYPE: TSP
+COMMENT: lowe_diag_row test file
+DIMENSION: 3
+EDGE_WEIGHT_TYPE: EXPLICIT
+EDGE_WEIGHT_FORMAT: LOWER_DIAG_ROW
+EDGE_WEIGHT_SECTION
+0
+2 0
+3 3 0
+EOF
+",
+            TspWriter::to_string(&tsp)
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use std::fmt::{Debug, Display};
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::str::FromStr;
+
+/// Type of a single edge weight.
+///
+/// Route lengths are summed in the wider `Length`, so even a route made of
+/// `dimension` maximal edges can't overflow.
+pub trait Weight:
+    Copy
+    + PartialEq
+    + PartialOrd
+    + Debug
+    + Display
+    + FromStr
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Send
+    + Sync
+    + 'static
+{
+    type Length: RouteLength + From<Self>;
+
+    const ZERO: Self;
+    const ONE: Self;
+    const MAX: Self;
+
+    /// Length of the little-endian encoding used by the binary cache.
+    const SIZE: usize;
+
+    /// Name of the type stored in the binary cache, stable across compilers
+    /// unlike `std::any::type_name`.
+    const TAG: &'static str;
+
+    /// Weights computed from coordinates are rounded by the TSPLIB rules
+    /// first, so every weight type gets the same distances.
+    fn from_u32(weight: u32) -> Self;
+
+    fn write_le(self, bytes: &mut Vec<u8>);
+
+    /// `bytes` has to be exactly `SIZE` long.
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+/// Sum of edge weights of a route or its part.
+pub trait RouteLength:
+    Copy
+    + PartialEq
+    + PartialOrd
+    + Debug
+    + Display
+    + Default
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + AddAssign
+    + SubAssign
+    + Send
+    + Sync
+    + 'static
+{
+}
+
+impl RouteLength for u64 {}
+impl RouteLength for u128 {}
+impl RouteLength for i128 {}
+impl RouteLength for f64 {}
+
+impl Weight for u32 {
+    type Length = u64;
+
+    const ZERO: u32 = 0;
+    const ONE: u32 = 1;
+    const MAX: u32 = u32::MAX;
+    const SIZE: usize = 4;
+    const TAG: &'static str = "u32";
+
+    fn from_u32(weight: u32) -> u32 {
+        weight
+    }
+
+    fn write_le(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn read_le(bytes: &[u8]) -> u32 {
+        u32::from_le_bytes(bytes.try_into().expect("weight has SIZE bytes"))
+    }
+}
+
+impl Weight for u64 {
+    type Length = u128;
+
+    const ZERO: u64 = 0;
+    const ONE: u64 = 1;
+    const MAX: u64 = u64::MAX;
+    const SIZE: usize = 8;
+    const TAG: &'static str = "u64";
+
+    fn from_u32(weight: u32) -> u64 {
+        weight.into()
+    }
+
+    fn write_le(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn read_le(bytes: &[u8]) -> u64 {
+        u64::from_le_bytes(bytes.try_into().expect("weight has SIZE bytes"))
+    }
+}
+
+impl Weight for i64 {
+    type Length = i128;
+
+    const ZERO: i64 = 0;
+    const ONE: i64 = 1;
+    const MAX: i64 = i64::MAX;
+    const SIZE: usize = 8;
+    const TAG: &'static str = "i64";
+
+    fn from_u32(weight: u32) -> i64 {
+        weight.into()
+    }
+
+    fn write_le(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn read_le(bytes: &[u8]) -> i64 {
+        i64::from_le_bytes(bytes.try_into().expect("weight has SIZE bytes"))
+    }
+}
+
+impl Weight for f64 {
+    type Length = f64;
+
+    const ZERO: f64 = 0.0;
+    const ONE: f64 = 1.0;
+    const MAX: f64 = f64::INFINITY;
+    const SIZE: usize = 8;
+    const TAG: &'static str = "f64";
+
+    fn from_u32(weight: u32) -> f64 {
+        weight.into()
+    }
+
+    fn write_le(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn read_le(bytes: &[u8]) -> f64 {
+        f64::from_le_bytes(bytes.try_into().expect("weight has SIZE bytes"))
+    }
+}